@@ -1,8 +1,10 @@
-use rand::Rng;
-use std::time::SystemTime;
-use std::{thread, time};
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+use std::time::{Duration,Instant};
 use bitfoo::{Node,Addr};
 
+// Timed set/get workload on a tree of random bits. Run with
+// --release for meaningful numbers.
 fn main() {
     println!("Size of instance is {}-bytes", std::mem::size_of::<Node>());
 
@@ -13,32 +15,43 @@ fn main() {
     // Set to the max level as bits could be any u64
     let level = 8;
     let mut node = Node::new(level);
-    println!("Setting {} bits on {} level node...", 
-             numbits, level);
-
-    // Randomly set the required number of bits
-    let mut rng = rand::thread_rng();
-    
-    println!("Start bit SET: {:?}", SystemTime::now());
-    for _n in 0..numbits {
-        let bitno: u64 = rng.gen();
-        if bitno % interval == 0 {
-            let addr = Addr::new(bitno);
-            println!("Setting bitno={} addr={:?}", bitno, addr);
-            node.set(&addr);
-        }
+
+    // Randomly set the required number of bits. Use a fixed
+    // seed so runs can be compared.
+    let mut rng = StdRng::seed_from_u64(0);
+    let bits: Vec<u64> = (0..numbits)
+        .map(|_| rng.gen::<u64>())
+        .filter(|bitno| bitno % interval == 0)
+        .collect();
+    println!("Setting {} bits on {} level node...",
+             bits.len(), level);
+
+    let start = Instant::now();
+    for bitno in &bits {
+        node.set(&Addr::new(*bitno));
     }
+    report("set", bits.len(), start.elapsed());
 
-    // Now go back and check those bits
-    // EYE - get() is much slower than set()
-    println!("Getting {} bits...", numbits);
-    println!("Start bit GET: {:?}", SystemTime::now());
+    // Now go back and check those bits, plus a dense run of
+    // low bits which are mostly missing
+    let start = Instant::now();
+    let mut found = 0;
+    for bitno in &bits {
+        found += node.get(&Addr::new(*bitno)) as usize;
+    }
+    report("get (set bits)", bits.len(), start.elapsed());
+    assert_eq!(found, bits.len());
+
+    let start = Instant::now();
+    let mut found = 0;
     for bitno in 0..numbits {
-        let addr = Addr::new(bitno);
-        let state = node.get(&addr);
-        println!("{} is {}", bitno, state);
+        found += node.get(&Addr::new(bitno)) as usize;
     }
-    println!("Time FINISHED: {:?}", SystemTime::now());
-    thread::sleep(time::Duration::from_secs(1000));
+    report("get (low bits)", numbits as usize, start.elapsed());
+    println!("{} low bits set", found);
 }
 
+fn report(name: &str, count: usize, elapsed: Duration) {
+    println!("{:>16}: {:>8} ops in {:>10.3?} ({:.1} ns/op)",
+             name, count, elapsed, elapsed.as_nanos() as f64 / count as f64);
+}
@@ -1,8 +1,10 @@
+use crate::BitFooError;
 use std::fmt;
 
 // Constant to get bit shift and mask for each 
 // level of the tree
 // (bit_offset, mask, max_bit, node_level)
+#[allow(clippy::identity_op, clippy::erasing_op)]
 const LEVEL_PARAM:[(u64, u64, u64, u8);10] = [
     (0,     0x3f, 0x3f, 1),        // 6 bits for within the leaf node
     (6+0*8, 0xff, 0x3f_ff, 1),     // 8 bits for all other levels
@@ -31,8 +33,7 @@ impl Addr {
             key: [0;10],
         };
 
-        for i in 0..addr.key.len() {
-            let param = LEVEL_PARAM[i as usize];
+        for (i, param) in LEVEL_PARAM.iter().enumerate() {
             addr.key[i] = ((bitno >> param.0) & param.1) as u8;
 
             // Set the level of the node that can contain this bitno
@@ -44,6 +45,36 @@ impl Addr {
         addr
     }
 
+    // Build an address from explicit keys, where keys[i] is the
+    // key at level i and keys above level are zero. Panics if a
+    // key is out of range.
+    pub fn from_keys(level: u8, keys: [u8;10]) -> Self {
+        Self::try_from_keys(level, keys).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // As above, but returning an error for a level outside 1-8
+    // or a key too large for its level
+    pub fn try_from_keys(level: u8, keys: [u8;10]) -> Result<Self, BitFooError> {
+        if !(1..=8).contains(&level) {
+            return Err(BitFooError::BadLevel(level));
+        }
+
+        let max = Addr::new(u64::MAX);
+        for (i, key) in keys.iter().enumerate() {
+            let limit = match i as u8 <= level {
+                true => max.key[i],
+                false => 0,
+            };
+            if *key > limit {
+                return Err(BitFooError::BadKey { level: i as u8, key: *key });
+            }
+        }
+
+        // Leading zero keys put us at a lower level
+        let addr = Addr { node_level: level, key: keys };
+        Ok(Addr::new(addr.bitno()))
+    }
+
     // Return cardinality for this node level
     pub fn cardinality(level: u8) -> u64 {
         match level {
@@ -95,6 +126,11 @@ impl Addr {
 
     pub fn set(&mut self, level: u8, key: u8) {
         self.key[level as usize] = key;
+
+        // A key set above our level moves us up to that level
+        if key > 0 && level > self.node_level {
+            self.node_level = level;
+        }
     }
 }
 
@@ -120,5 +156,7 @@ impl Clone for Addr {
 }
 
 #[cfg(test)]
+// Older tests spell out expected values long-hand
+#[allow(clippy::useless_vec)]
 #[path = "./tests/addr_test.rs"]
 mod tests;
\ No newline at end of file
@@ -0,0 +1,97 @@
+use crate::BitFooVec;
+use std::ops::RangeInclusive;
+
+// Allocator for unique u64 ids. Allocated ids are set bits, so
+// fully allocated subtrees collapse into KeyIndex runs which the
+// search for a free id skips in one step per level.
+#[derive(Debug)]
+pub struct BitFooAllocator {
+    bits: BitFooVec,
+}
+
+// Public interface
+impl BitFooAllocator {
+    pub fn new() -> Self {
+        BitFooAllocator {
+            bits: BitFooVec::new(),
+        }
+    }
+
+    // Allocate the lowest free id, or None if every id is taken
+    pub fn allocate(&mut self) -> Option<u64> {
+        self.allocate_at_least(0)
+    }
+
+    // Allocate the lowest free id that is at least min
+    pub fn allocate_at_least(&mut self, min: u64) -> Option<u64> {
+        let id = self.bits.next_clear(min)?;
+        self.bits.set(id);
+        Some(id)
+    }
+
+    // Allocate the lowest block of len contiguous free ids
+    pub fn allocate_range(&mut self, len: u64) -> Option<RangeInclusive<u64>> {
+        let mut start = self.bits.next_clear(0)?;
+        let extent = len.checked_sub(1)?;
+
+        loop {
+            // Check nothing is allocated between start and the 
+            // end of the block, otherwise jump past the allocated ids
+            let end = start.checked_add(extent)?;
+            match self.bits.next_set(start) {
+                Some(bitno) if bitno <= end => {
+                    start = self.bits.next_clear(bitno)?;
+                },
+                _ => {
+                    self.bits.set_range(start..=end);
+                    return Some(start..=end);
+                },
+            }
+        }
+    }
+
+    // Free an id. Returns false if it was not allocated.
+    pub fn free(&mut self, id: u64) -> bool {
+        let allocated = self.bits.get(id);
+        self.bits.clear(id);
+        allocated
+    }
+
+    // Free all ids in the range
+    pub fn free_range(&mut self, range: RangeInclusive<u64>) {
+        self.bits.clear_range(range);
+    }
+
+    // Return true if the id is allocated
+    pub fn is_allocated(&self, id: u64) -> bool {
+        self.bits.get(id)
+    }
+
+    // Return the number of allocated ids
+    pub fn count(&self) -> u128 {
+        self.bits.count()
+    }
+
+    // Return the allocated ids as a bit vector
+    pub fn bits(&self) -> &BitFooVec {
+        &self.bits
+    }
+}
+
+impl Default for BitFooAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for BitFooAllocator {
+    fn clone(&self) -> BitFooAllocator {
+        BitFooAllocator { 
+            bits: self.bits.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/allocator_test.rs"]
+mod tests;
@@ -1,7 +1,17 @@
-use crate::{Node,Addr};
+use crate::{Node,NodeIterator,NodeRangeIterator,Addr};
+use crate::node::{SetOp,cmp_ranges};
+use crate::{BitFooError,BitFooStats};
+use crate::{codec,frozen};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::hash::{Hash,Hasher};
+use std::io::{Read,Write};
+use std::iter::FromIterator;
+use std::ops::{Bound,RangeBounds,Range,RangeInclusive};
+use std::ops::{BitAnd,BitOr,BitXor,Sub};
+use std::ops::{BitAndAssign,BitOrAssign,BitXorAssign,SubAssign};
 
 // Main container for the bit vector
-#[derive(Debug)]
 pub struct BitFooVec {
     root_node: Node,
 }
@@ -24,28 +34,25 @@ impl BitFooVec {
     // is too high
     pub fn set(&mut self, bitno: u64) {
         let addr = Addr::new(bitno);
-        let mut level = self.level();
-
-        while addr.node_level > level {
-            // Replace root with new one at next level up
-            level += 1;
-            let old_root = std::mem::replace(
-                &mut self.root_node, Node::new(level));
-
-            // Set old_root to be child of new root 
-            self.root_node.add_node(old_root); 
-        }
+        self.grow(addr.node_level);
         self.root_node.set(&addr);
+        self.check();
     }
 
     // Return state of this bit
     pub fn get(&self, bitno: u64) -> bool {
+        self.try_get(bitno).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Return the state of the bit, or an error if the tree is
+    // damaged along the way
+    pub fn try_get(&self, bitno: u64) -> Result<bool, BitFooError> {
         let addr = Addr::new(bitno);
         if addr.node_level > self.level() {
-            false
+            Ok(false)
         }
         else {
-            self.root_node.get(&addr)
+            self.root_node.try_get(&addr)
         }
     }
 
@@ -55,8 +62,510 @@ impl BitFooVec {
         let addr = Addr::new(bitno);
         if addr.node_level <= self.level() {
             self.root_node.clear(&addr);
+            self.shrink();
+            self.check();
+        }
+    }
+
+    // Set all bits in the range. Whole subtrees in the range are
+    // written as runs, so this is far cheaper than calling set() 
+    // for each bit.
+    pub fn set_range<R: RangeBounds<u64>>(&mut self, range: R) {
+        if let Some((start, end)) = bounds(&range) {
+            self.grow(Addr::new(end).node_level);
+            self.root_node.set_range(&Addr::new(start), end);
+            self.check();
+        }
+    }
+
+    // Clear all bits in the range
+    pub fn clear_range<R: RangeBounds<u64>>(&mut self, range: R) {
+        // Bits above the root are already implied to be 0
+        match bounds(&range) {
+            Some((start, end)) if start <= self.max_bitno() => {
+                let end = end.min(self.max_bitno());
+                self.root_node.clear_range(&Addr::new(start), end);
+                self.shrink();
+                self.check();
+            },
+            _ => (),
+        }
+    }
+
+    // Release unused capacity throughout the tree
+    pub fn shrink_to_fit(&mut self) {
+        self.root_node.shrink_to_fit();
+        self.check();
+    }
+
+    // Return the first set bit at or after bitno
+    pub fn next_set(&self, bitno: u64) -> Option<u64> {
+        if bitno > self.max_bitno() {
+            None
+        }
+        else {
+            self.root_node.next_set(&Addr::new(bitno))
+        }
+    }
+
+    // Return the last set bit at or before bitno
+    pub fn prev_set(&self, bitno: u64) -> Option<u64> {
+        let bitno = bitno.min(self.max_bitno());
+        self.root_node.prev_set(&Addr::new(bitno))
+    }
+
+    // Return the first clear bit at or after bitno
+    pub fn next_clear(&self, bitno: u64) -> Option<u64> {
+        if bitno > self.max_bitno() {
+            Some(bitno)
+        }
+        else {
+            // Bits above the root are all clear
+            self.root_node.next_clear(&Addr::new(bitno))
+                .or_else(|| self.max_bitno().checked_add(1))
+        }
+    }
+
+    // Return the last clear bit at or before bitno
+    pub fn prev_clear(&self, bitno: u64) -> Option<u64> {
+        if bitno > self.max_bitno() {
+            Some(bitno)
+        }
+        else {
+            self.root_node.prev_clear(&Addr::new(bitno))
+        }
+    }
+
+    // Return an iterator over the set bits in ascending order
+    pub fn iter(&self) -> NodeIterator<'_> {
+        // Root node always starts at bit 0
+        self.root_node.iter(Addr::new(0))
+    }
+
+    // Return an iterator over the set bits within the range.
+    // We seek straight to the start rather than walking from bit 0.
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> NodeIterator<'_> {
+        // Nothing is set above the highest bit the root can hold.
+        // NOTE: The node iterator is empty if start > end.
+        let (start, end) = match bounds(&range) {
+            Some((start, end)) => (start, end.min(self.max_bitno())),
+            None => (1, 0),
+        };
+        self.root_node.iter_range(Addr::new(start), end)
+    }
+
+    // Same as range(), named to go with iter() and iter_from()
+    pub fn iter_range<R: RangeBounds<u64>>(&self, range: R) -> NodeIterator<'_> {
+        self.range(range)
+    }
+
+    // Return an iterator over the set bits from bitno onwards
+    pub fn iter_from(&self, bitno: u64) -> NodeIterator<'_> {
+        self.range(bitno..)
+    }
+
+    // Return the number of bits set. This is a u128 as a
+    // full vector holds 2^64 bits.
+    pub fn count(&self) -> u128 {
+        self.root_node.count()
+    }
+
+    // Return the number of bits set within the range
+    pub fn count_range<R: RangeBounds<u64>>(&self, range: R) -> u128 {
+        match bounds(&range) {
+            Some((start, end)) if start <= self.max_bitno() => {
+                let end = end.min(self.max_bitno());
+                self.root_node.count_range(&Addr::new(start), end)
+            },
+            _ => 0,
+        }
+    }
+
+    // Return the number of bits set below bitno
+    pub fn rank(&self, bitno: u64) -> u64 {
+        // NOTE: Fewer than 2^64 bits can be below any bitno
+        if bitno > self.max_bitno() {
+            self.root_node.count() as u64
+        }
+        else {
+            self.root_node.rank(&Addr::new(bitno)) as u64
+        }
+    }
+
+    // Return the position of the k'th set bit (counting 
+    // from 0), or None if there are k or fewer bits set
+    pub fn select(&self, k: u64) -> Option<u64> {
+        self.root_node.select(&Addr::new(0), k as u128)
+    }
+
+    // Return an iterator over the maximal intervals of 
+    // contiguous set bits in ascending order
+    pub fn ranges(&self) -> NodeRangeIterator<'_> {
+        self.root_node.ranges(Addr::new(0))
+    }
+
+    // Return the bytes allocated on the heap by the tree
+    pub fn heap_size(&self) -> usize {
+        self.root_node.heap_size()
+    }
+
+    // Return the shape and memory use of the tree
+    pub fn stats(&self) -> BitFooStats {
+        let mut stats = BitFooStats {
+            root_level: self.level(),
+            ..BitFooStats::default()
+        };
+        self.root_node.add_stats(&mut stats);
+        stats
+    }
+
+    // Check the tree invariants, returning the first problem found
+    pub fn validate(&self) -> Result<(), BitFooError> {
+        if self.level() > 8 {
+            return Err(BitFooError::Invalid {
+                level: self.level(),
+                bitno: 0,
+                reason: "root level above 8",
+            });
+        }
+        self.root_node.validate()
+    }
+
+    // Return true if every bit we hold is also set in other
+    pub fn is_subset(&self, other: &BitFooVec) -> bool {
+        self.root_node.is_subset(&other.root_node)
+    }
+
+    // Return true if every bit in other is also set here
+    pub fn is_superset(&self, other: &BitFooVec) -> bool {
+        self.root_node.is_superset(&other.root_node)
+    }
+
+    // Return true if we share no bits with other
+    pub fn is_disjoint(&self, other: &BitFooVec) -> bool {
+        self.root_node.is_disjoint(&other.root_node)
+    }
+
+    // Return true if we share any bit with other
+    pub fn intersects(&self, other: &BitFooVec) -> bool {
+        self.root_node.intersects(&other.root_node)
+    }
+
+    // Return the number of bits set in both, without building
+    // the intersection
+    pub fn intersection_count(&self, other: &BitFooVec) -> u128 {
+        self.root_node.intersection_count(&other.root_node)
+    }
+
+    // Build from a list of ranges in any order. The ranges are
+    // sorted and merged first so each bit is only written once.
+    pub fn from_ranges<I>(ranges: I) -> Self
+        where I: IntoIterator<Item = RangeInclusive<u64>>
+    {
+        let mut ranges: Vec<(u64, u64)> = ranges.into_iter()
+            .filter(|range| !range.is_empty())
+            .map(|range| (*range.start(), *range.end()))
+            .collect();
+        ranges.sort_unstable();
+
+        let mut bv = BitFooVec::new();
+        let mut pending: Option<(u64, u64)> = None;
+        for (start, end) in ranges {
+            match pending {
+                Some((pstart, pend)) if start <= pend.saturating_add(1) => {
+                    // Overlapping or adjacent - extend the pending range
+                    pending = Some((pstart, pend.max(end)));
+                },
+                Some((pstart, pend)) => {
+                    bv.set_range(pstart..=pend);
+                    pending = Some((start, end));
+                },
+                None => pending = Some((start, end)),
+            }
+        }
+        if let Some((start, end)) = pending {
+            bv.set_range(start..=end);
+        }
+        bv
+    }
+
+    // Build from bits in ascending order (repeats are allowed).
+    // Leaf words and child nodes are appended to the end of the
+    // tree rather than searched for and inserted.
+    pub fn from_sorted_iter<I>(bits: I) -> Result<Self, BitFooError>
+        where I: IntoIterator<Item = u64>
+    {
+        let mut bv = BitFooVec::new();
+        let mut word: Option<(u64, u64)> = None;   // Start bit and bits
+
+        for bitno in bits {
+            let start = bitno & !0x3f;
+            word = match word {
+                Some((prev, _bits)) if start < prev => {
+                    return Err(BitFooError::Unsorted(bitno));
+                },
+                Some((prev, bits)) if start == prev => {
+                    if bits >> (bitno & 0x3f) > 1 {
+                        return Err(BitFooError::Unsorted(bitno));
+                    }
+                    Some((prev, bits | 1 << (bitno & 0x3f)))
+                },
+                Some((prev, bits)) => {
+                    bv.push_word(prev, bits);
+                    Some((start, 1 << (bitno & 0x3f)))
+                },
+                None => Some((start, 1 << (bitno & 0x3f))),
+            };
+        }
+
+        if let Some((start, bits)) = word {
+            bv.push_word(start, bits);
+        }
+        Ok(bv)
+    }
+
+    // Write in the versioned binary format described in codec.rs.
+    // Many small writes are made, so a buffered writer is best.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), BitFooError> {
+        codec::write_tree(&self.root_node, writer)
+    }
+
+    // Read a vector written by write_to(). The tree is rebuilt
+    // directly and corrupt input is reported as an error.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, BitFooError> {
+        Ok(BitFooVec {
+            root_node: codec::read_tree(reader)?,
+        })
+    }
+
+    // Return the frozen layout read in place by FrozenBitFooVec
+    pub fn freeze(&self) -> Vec<u8> {
+        frozen::freeze(&self.root_node)
+    }
+}
+
+// Private helpers
+impl BitFooVec {
+    // Insert parent nodes above the root until it is at 
+    // the given level
+    fn grow(&mut self, level: u8) {
+        while self.level() < level {
+            // Replace root with new one at next level up
+            let next_level = self.level() + 1;
+            let old_root = std::mem::replace(
+                &mut self.root_node, Node::new(next_level));
+
+            // Set old_root to be child of new root. An empty root
+            // is dropped and a full one becomes a run.
+            if old_root.index.is_all_runs() {
+                self.root_node.index.run(&Addr::new(0));
+            }
+            else if !old_root.is_empty() {
+                self.root_node.add_node(old_root); 
+            }
+        }
+    }
+
+    // Set the given bits in the leaf word holding bitno
+    pub (crate) fn set_word(&mut self, bitno: u64, bits: u64) {
+        let addr = Addr::new(bitno);
+        self.grow(addr.node_level);
+        self.root_node.set_word(&addr, bits);
+        self.check();
+    }
+
+    // Append the given bits in a leaf word at or after our last bit
+    fn push_word(&mut self, bitno: u64, bits: u64) {
+        let addr = Addr::new(bitno);
+        self.grow(addr.node_level);
+        self.root_node.push_word(&addr, bits);
+        self.check();
+    }
+
+    // Return our root node raised to the given level
+    fn root_at(&self, level: u8) -> Cow<'_, Node> {
+        if self.level() >= level {
+            Cow::Borrowed(&self.root_node)
+        }
+        else {
+            let mut bv = self.clone();
+            bv.grow(level);
+            Cow::Owned(bv.root_node)
+        }
+    }
+
+    // Combine with another vector using the set operation.
+    // Roots must be at the same level to merge, so the lower
+    // one is raised first.
+    fn merge(&self, other: &BitFooVec, op: SetOp) -> BitFooVec {
+        let level = self.level().max(other.level());
+        let lhs = self.root_at(level);
+        let rhs = other.root_at(level);
+        let mut bv = BitFooVec {
+            root_node: lhs.merge(&rhs, op),
+        };
+        bv.shrink();
+        bv.check();
+        bv
+    }
+
+    // Drop root levels that only hold key 0, so the root is no
+    // higher than our highest bit needs. This undoes grow().
+    fn shrink(&mut self) {
+        while self.level() > 1 {
+            let index = &self.root_node.index;
+            if (&index.nodes | &index.runs).next_set(1).is_some() {
+                break;
+            }
+
+            // Key 0 becomes the root. A run becomes a full node and
+            // an empty root starts over at level 1.
+            let level = self.level() - 1;
+            let is_run = index.runs.get(0);
+            let old_root = std::mem::replace(&mut self.root_node, Node::new(1));
+            if is_run {
+                self.root_node = Node::new(level);
+                self.root_node.index.set_all_runs();
+            }
+            else if let Some(child) = old_root.into_child() {
+                self.root_node = child;
+            }
         }
     }
+
+    // Highest bitno that can be held by the root node
+    fn max_bitno(&self) -> u64 {
+        Addr::cardinality(self.level() + 1)
+    }
+
+    // With the debug-validate feature, panic as soon as a
+    // mutation leaves the tree broken
+    fn check(&self) {
+        #[cfg(feature = "debug-validate")]
+        if let Err(err) = self.validate() {
+            panic!("BitFooVec is invalid after update: {}", err);
+        }
+    }
+}
+
+// Convert range bounds to an inclusive (start, end) pair.
+// Returns None if the range is empty.
+pub (crate) fn bounds<R: RangeBounds<u64>>(range: &R) -> Option<(u64, u64)> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => *end,
+        Bound::Excluded(end) => end.checked_sub(1)?,
+        Bound::Unbounded => u64::MAX,
+    };
+
+    if start <= end {
+        Some((start, end))
+    }
+    else {
+        None
+    }
+}
+
+impl<'a> IntoIterator for &'a BitFooVec {
+    type Item = u64;
+    type IntoIter = NodeIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<u64> for BitFooVec {
+    fn from_iter<I: IntoIterator<Item = u64>>(bits: I) -> Self {
+        let mut bv = BitFooVec::new();
+        bv.extend(bits);
+        bv
+    }
+}
+
+// Bits in the same leaf word are gathered up and set together
+impl Extend<u64> for BitFooVec {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, bits: I) {
+        let mut word: Option<(u64, u64)> = None;   // Start bit and bits
+
+        for bitno in bits {
+            let start = bitno & !0x3f;
+            word = match word {
+                Some((prev, bits)) if prev == start => {
+                    Some((prev, bits | 1 << (bitno & 0x3f)))
+                },
+                Some((prev, bits)) => {
+                    self.set_word(prev, bits);
+                    Some((start, 1 << (bitno & 0x3f)))
+                },
+                None => Some((start, 1 << (bitno & 0x3f))),
+            };
+        }
+
+        if let Some((start, bits)) = word {
+            self.set_word(start, bits);
+        }
+    }
+}
+
+impl Extend<Range<u64>> for BitFooVec {
+    fn extend<I: IntoIterator<Item = Range<u64>>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.set_range(range);
+        }
+    }
+}
+
+impl Extend<RangeInclusive<u64>> for BitFooVec {
+    fn extend<I: IntoIterator<Item = RangeInclusive<u64>>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.set_range(range);
+        }
+    }
+}
+
+// Comparison interface. As for Node, vectors holding the same bits
+// are equal whatever their root level or run structure.
+impl PartialEq for BitFooVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.ranges().eq(other.ranges())
+    }
+}
+
+impl Eq for BitFooVec {}
+
+impl Hash for BitFooVec {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut count: usize = 0;
+        for range in self.ranges() {
+            range.start().hash(state);
+            range.end().hash(state);
+            count += 1;
+        }
+        count.hash(state);
+    }
+}
+
+impl PartialOrd for BitFooVec {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BitFooVec {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_ranges(self.ranges(), other.ranges())
+    }
+}
+
+impl Default for BitFooVec {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Clone for BitFooVec {
@@ -67,6 +576,48 @@ impl Clone for BitFooVec {
     }
 }
 
+// Implement a set operator for owned and borrowed 
+// vectors, along with the assigning form
+macro_rules! set_op {
+    ($op_trait:ident, $op_fn:ident, 
+     $assign_trait:ident, $assign_fn:ident, $set_op:expr) => 
+    {
+        impl $op_trait for &BitFooVec {
+            type Output = BitFooVec;
+
+            fn $op_fn(self, rhs: Self) -> Self::Output {
+                self.merge(rhs, $set_op)
+            }
+        }
+
+        impl $op_trait for BitFooVec {
+            type Output = Self;
+
+            fn $op_fn(self, rhs: Self) -> Self::Output {
+                self.merge(&rhs, $set_op)
+            }
+        }
+
+        impl $assign_trait<&BitFooVec> for BitFooVec {
+            fn $assign_fn(&mut self, rhs: &BitFooVec) {
+                *self = self.merge(rhs, $set_op);
+            }
+        }
+
+        impl $assign_trait for BitFooVec {
+            fn $assign_fn(&mut self, rhs: BitFooVec) {
+                *self = self.merge(&rhs, $set_op);
+            }
+        }
+    };
+}
+
+// Intersection, union, symmetric difference and difference
+set_op!(BitAnd, bitand, BitAndAssign, bitand_assign, SetOp::And);
+set_op!(BitOr, bitor, BitOrAssign, bitor_assign, SetOp::Or);
+set_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, SetOp::Xor);
+set_op!(Sub, sub, SubAssign, sub_assign, SetOp::Sub);
+
 #[cfg(test)]
 #[path = "./tests/bitfoovec_test.rs"]
 mod tests;
\ No newline at end of file
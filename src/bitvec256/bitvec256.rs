@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
+use std::convert::TryInto;
 use std::fmt;
+use std::hash::{Hash,Hasher};
 use crate::bitvec256::iter::BitVec256Iterator;
 
 // Static 256 bit vector
@@ -55,34 +58,120 @@ impl BitVec256 {
         self.data[0] & self.data[1] & self.data[2] & self.data[3] == u64::MAX
     }
 
+    // Return the number of bits set
+    pub fn count_ones(&self) -> u32 {
+        self.data.iter().map(|word| word.count_ones()).sum()
+    }
+
+    // Return the first set bit at or after bitno
+    pub fn next_set(&self, bitno: u8) -> Option<u8> {
+        self.iter_range(bitno, u8::MAX).next()
+    }
+
+    // Return the last set bit at or before bitno
+    pub fn prev_set(&self, bitno: u8) -> Option<u8> {
+        self.iter_range(0, bitno).next_back()
+    }
+
+    // Return the first clear bit at or after bitno
+    pub fn next_clear(&self, bitno: u8) -> Option<u8> {
+        let (first_word, offset) = self.location(bitno);
+        for wordno in first_word..4 {
+            // Scan the inverted word, ignoring bits before bitno
+            let mut word = !self.data[wordno as usize];
+            if wordno == first_word {
+                word &= u64::MAX << offset;
+            }
+            if word != 0 {
+                return Some(wordno * 64 + word.trailing_zeros() as u8);
+            }
+        }
+        None
+    }
+
+    // Return the last clear bit at or before bitno
+    pub fn prev_clear(&self, bitno: u8) -> Option<u8> {
+        let (last_word, offset) = self.location(bitno);
+        for wordno in (0..=last_word).rev() {
+            // Scan the inverted word, ignoring bits after bitno
+            let mut word = !self.data[wordno as usize];
+            if wordno == last_word {
+                word &= u64::MAX >> (63 - offset);
+            }
+            if word != 0 {
+                return Some(wordno * 64 + 63 - word.leading_zeros() as u8);
+            }
+        }
+        None
+    }
+
     // Return the offset for the given key. This is the
     // number of bits set before our key. If key is not
     // set, return Err with the offset
     pub fn offset(&self, key: u8) -> Result<u8,u8> {
-        let mut offset:u8 = 0;
-        for currkey in self.iter() {
-            if currkey == key {
-                // Found our key
-                return Ok(offset);
-            }
-            else if currkey > key {
-                // We stepped past our key
-                break;
-            }
+        match self.get(key) {
+            true => Ok(self.rank(key)),
+            false => Err(self.rank(key)),
+        }
+    }
+
+    // Return the number of bits set below bitno
+    pub fn rank(&self, bitno: u8) -> u8 {
+        // Count the whole words before ours, then the
+        // bits below bitno in its own word
+        let (word, offset) = self.location(bitno);
+        let before: u32 = self.data[..word as usize].iter()
+            .map(|word| word.count_ones())
+            .sum();
+        let mask = !(u64::MAX << offset);
+        (before + (self.data[word as usize] & mask).count_ones()) as u8
+    }
 
-            offset += 1;
+    // Return the position of the k'th set bit (counting
+    // from 0), or None if there are k or fewer bits set
+    pub fn select(&self, k: u8) -> Option<u8> {
+        let mut k = k as u32;
+        for (wordno, word) in self.data.iter().enumerate() {
+            let count = word.count_ones();
+            if k < count {
+                // Drop the lowest k bits of this word
+                let mut word = *word;
+                for _ in 0..k {
+                    word &= word - 1;
+                }
+                return Some(wordno as u8 * 64 + word.trailing_zeros() as u8);
+            }
+            k -= count;
         }
+        None
+    }
+
+    // Return the lowest set bit
+    pub fn first_set(&self) -> Option<u8> {
+        self.data.iter().enumerate()
+            .find(|(_wordno, word)| **word != 0)
+            .map(|(wordno, word)| wordno as u8 * 64 + word.trailing_zeros() as u8)
+    }
 
-        // Our key is not set
-        Err(offset)
+    // Return the highest set bit
+    pub fn last_set(&self) -> Option<u8> {
+        self.data.iter().enumerate().rev()
+            .find(|(_wordno, word)| **word != 0)
+            .map(|(wordno, word)| wordno as u8 * 64 + 63 - word.leading_zeros() as u8)
     }
 
     // Return an iterator
-    pub fn iter(&self) -> BitVec256Iterator {
+    pub fn iter(&self) -> BitVec256Iterator<'_> {
+        self.iter_range(0, u8::MAX)
+    }
+
+    // Return an iterator over the set bits from start to 
+    // end (inclusive). Empty if start > end.
+    pub fn iter_range(&self, start: u8, end: u8) -> BitVec256Iterator<'_> {
         BitVec256Iterator {
             vec: self,
-            bitno: 0,
-            wordno: 0,
+            front: start as u16,
+            back: (end as u16 + 1).max(start as u16),
         }
     }
 
@@ -90,6 +179,11 @@ impl BitVec256 {
     pub fn raw_data(&self, offset: u8) -> u64 {
         self.data[offset as usize]
     }
+
+    // Build from the raw words, lowest bits first
+    pub (crate) fn from_raw_data(data: [u64;4]) -> Self {
+        BitVec256 { data }
+    }
 }
 
 // Private interface
@@ -97,17 +191,99 @@ impl BitVec256 {
     pub (super) fn location(&self, bitno: u8) -> (u8,u8) {
         (bitno / 64, bitno % 64)
     }
+
+    // Move every bit up by count, dropping those shifted
+    // past the top
+    pub (super) fn shift_up(&mut self, count: u32) {
+        let (words, offset) = ((count / 64) as usize, count % 64);
+        for i in (0..4).rev() {
+            self.data[i] = match i.checked_sub(words) {
+                Some(src) if offset == 0 => self.data[src],
+                Some(0) => self.data[0] << offset,
+                Some(src) => {
+                    self.data[src] << offset | self.data[src - 1] >> (64 - offset)
+                },
+                None => 0,
+            };
+        }
+    }
+
+    // Move every bit down by count, dropping those shifted
+    // past the bottom
+    pub (super) fn shift_down(&mut self, count: u32) {
+        let (words, offset) = ((count / 64) as usize, count % 64);
+        for i in 0..4 {
+            let src = i + words;
+            self.data[i] = match src {
+                _ if src > 3 => 0,
+                _ if offset == 0 => self.data[src],
+                3 => self.data[3] >> offset,
+                _ => self.data[src] >> offset | self.data[src + 1] << (64 - offset),
+            };
+        }
+    }
+}
+
+// Conversion interface. Words and bytes hold the lowest bits first.
+impl From<[u64;4]> for BitVec256 {
+    fn from(data: [u64;4]) -> Self {
+        Self::from_raw_data(data)
+    }
+}
+
+impl From<[u8;32]> for BitVec256 {
+    fn from(bytes: [u8;32]) -> Self {
+        let mut data = [0;4];
+        for (word, chunk) in data.iter_mut().zip(bytes.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Self::from_raw_data(data)
+    }
+}
+
+// Default interface
+impl Default for BitVec256 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // Clone interface
 impl Clone for BitVec256 {
     fn clone(&self) -> BitVec256 {
         BitVec256 {
-            data: self.data.clone(),
+            data: self.data,
         }
     }
 }
 
+// Comparison interface. Ordered by the sequence of set bits.
+impl PartialEq for BitVec256 {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl Eq for BitVec256 {}
+
+impl Hash for BitVec256 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl PartialOrd for BitVec256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BitVec256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
 // Debug interface
 impl fmt::Debug for BitVec256 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
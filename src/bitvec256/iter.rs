@@ -1,44 +1,75 @@
 use std::iter::Iterator;
 use crate::BitVec256;
 
-// Iterator over set bits
+// Iterator over set bits in the range front..back
 pub struct BitVec256Iterator<'a> {
     pub (super) vec: &'a BitVec256,
-    pub (super) bitno: u8,
-    pub (super) wordno: u8,
+    pub (super) front: u16,  // Next bitno to check
+    pub (super) back: u16,   // One past the last bitno to check
 }
 
 impl<'a> Iterator for BitVec256Iterator<'a> {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Count leading zeros and shift until
+        // Count trailing zeros and skip ahead until
         // no more found
-        while self.wordno < 4 {
-            let word = self.vec.data[self.wordno as usize] >> self.bitno;
-            let offset = word.trailing_zeros() as u8;
+        while self.front < self.back {
+            let wordno = (self.front / 64) as usize;
+            let bitno = self.front % 64;
+            let word = self.vec.data[wordno] >> bitno;
 
-            if offset >= 64 {
+            if word == 0 {
                 // Done this word - on to the next
-                self.wordno += 1;
-                self.bitno = 0;
+                self.front = (wordno as u16 + 1) * 64;
             }
             else {
                 // Calculate the return value
-                let currbit = self.bitno + offset;
-                let retval = currbit + (self.wordno * 64);
-
-                // Increment our iterator bitno/wordno
-                self.bitno = currbit + 1;
-                if self.bitno >= 64 {
-                    self.wordno += 1;
-                    self.bitno = 0;
+                let currbit = self.front + word.trailing_zeros() as u16;
+                if currbit >= self.back {
+                    break;
                 }
-                return Some(retval);
+
+                self.front = currbit + 1;
+                return Some(currbit as u8);
+            }
+        }
+
+        // No more found if we got here
+        self.front = self.back;
+        None
+    }
+}
+
+impl<'a> DoubleEndedIterator for BitVec256Iterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // Count leading zeros and skip back until
+        // no more found
+        while self.front < self.back {
+            let last = self.back - 1;
+            let wordno = (last / 64) as usize;
+            let bitno = last % 64;
+            let word = self.vec.data[wordno] << (63 - bitno);
+
+            if word == 0 {
+                // Done this word - on to the previous
+                self.back = wordno as u16 * 64;
+            }
+            else {
+                // Calculate the return value
+                let offset = word.leading_zeros() as u16;
+                if offset > last - self.front {
+                    break;
+                }
+
+                let currbit = last - offset;
+                self.back = currbit;
+                return Some(currbit as u8);
             }
         }
 
         // No more found if we got here
+        self.back = self.front;
         None
     }
 }
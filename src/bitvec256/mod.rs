@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod bitvec256;
 mod ops;
 mod iter;
@@ -6,4 +7,7 @@ pub use self::bitvec256::BitVec256;
 pub use self::iter::BitVec256Iterator;
 
 #[cfg(test)]
+// Older tests spell out expected values long-hand
+#[allow(clippy::bool_assert_comparison, clippy::needless_range_loop,
+    clippy::precedence)]
 mod tests;
\ No newline at end of file
@@ -1,4 +1,5 @@
-use std::ops::{Index,BitAnd,BitOr};
+use std::ops::{Index,BitAnd,BitOr,BitXor,Sub,Not,Shl,Shr};
+use std::ops::{BitAndAssign,BitOrAssign,BitXorAssign,SubAssign,ShlAssign,ShrAssign};
 use crate::BitVec256;
 
 // Static references for [] return values
@@ -17,54 +18,107 @@ impl Index<u8> for BitVec256 {
     }
 }
 
-// Override & operator
-impl BitAnd for BitVec256 {
-    type Output = Self;
+// Implement a word by word operator for owned and borrowed
+// vectors, along with the assigning form
+macro_rules! word_op {
+    ($op_trait:ident, $op_fn:ident,
+     $assign_trait:ident, $assign_fn:ident, $word_op:expr) =>
+    {
+        impl $assign_trait<&BitVec256> for BitVec256 {
+            fn $assign_fn(&mut self, rhs: &BitVec256) {
+                for i in 0..rhs.data.len() {
+                    self.data[i] = $word_op(self.data[i], rhs.data[i]);
+                }
+            }
+        }
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        let mut result = BitVec256::new();
-        for i in 0..rhs.data.len() {
-            result.data[i] = self.data[i] & rhs.data[i];
+        impl $assign_trait for BitVec256 {
+            fn $assign_fn(&mut self, rhs: BitVec256) {
+                self.$assign_fn(&rhs);
+            }
         }
-        result
-    }
-}
 
-// Override & operator for references
-impl BitAnd for &BitVec256 {
-    type Output = BitVec256;
+        impl $op_trait for &BitVec256 {
+            type Output = BitVec256;
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        let mut result = BitVec256::new();
-        for i in 0..rhs.data.len() {
-            result.data[i] = self.data[i] & rhs.data[i];
+            fn $op_fn(self, rhs: Self) -> Self::Output {
+                let mut result = self.clone();
+                result.$assign_fn(rhs);
+                result
+            }
         }
-        result
-    }
+
+        impl $op_trait for BitVec256 {
+            type Output = Self;
+
+            fn $op_fn(mut self, rhs: Self) -> Self::Output {
+                self.$assign_fn(&rhs);
+                self
+            }
+        }
+    };
 }
 
-// Override | operator
-impl BitOr for BitVec256 {
+// Override &, |, ^ and - (and not) operators
+word_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a: u64, b: u64| a & b);
+word_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a: u64, b: u64| a | b);
+word_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a: u64, b: u64| a ^ b);
+word_op!(Sub, sub, SubAssign, sub_assign, |a: u64, b: u64| a & !b);
+
+// Override ! operator
+impl Not for BitVec256 {
     type Output = Self;
 
-    fn bitor(self, rhs: Self) -> Self::Output {
-        let mut result = BitVec256::new();
-        for i in 0..rhs.data.len() {
-            result.data[i] = self.data[i] | rhs.data[i];
+    fn not(mut self) -> Self::Output {
+        for word in self.data.iter_mut() {
+            *word = !*word;
         }
-        result
+        self
     }
 }
 
-// Override | operator for references
-impl BitOr for &BitVec256 {
+impl Not for &BitVec256 {
     type Output = BitVec256;
 
-    fn bitor(self, rhs: Self) -> Self::Output {
-        let mut result = BitVec256::new();
-        for i in 0..rhs.data.len() {
-            result.data[i] = self.data[i] | rhs.data[i];
-        }
-        result
+    fn not(self) -> Self::Output {
+        !self.clone()
     }
-}
\ No newline at end of file
+}
+
+// Implement a shift by bit count for owned and borrowed
+// vectors, along with the assigning form. Bits shifted
+// out of the 256 are lost.
+macro_rules! shift_op {
+    ($op_trait:ident, $op_fn:ident,
+     $assign_trait:ident, $assign_fn:ident, $shift:ident) =>
+    {
+        impl $assign_trait<u32> for BitVec256 {
+            fn $assign_fn(&mut self, count: u32) {
+                self.$shift(count);
+            }
+        }
+
+        impl $op_trait<u32> for &BitVec256 {
+            type Output = BitVec256;
+
+            fn $op_fn(self, count: u32) -> Self::Output {
+                let mut result = self.clone();
+                result.$shift(count);
+                result
+            }
+        }
+
+        impl $op_trait<u32> for BitVec256 {
+            type Output = Self;
+
+            fn $op_fn(mut self, count: u32) -> Self::Output {
+                self.$shift(count);
+                self
+            }
+        }
+    };
+}
+
+// Override << (towards higher bits) and >> operators
+shift_op!(Shl, shl, ShlAssign, shl_assign, shift_up);
+shift_op!(Shr, shr, ShrAssign, shr_assign, shift_down);
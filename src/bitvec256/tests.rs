@@ -173,7 +173,229 @@ fn offset() {
     a.set(200);
     a.set(255);
 
-    // EYE Need a full test including Err and Ok
-    assert_eq!(a.offset(0).unwrap(), 0);
+    assert_eq!(a.offset(0), Ok(0));
+    assert_eq!(a.offset(1), Err(1));
+    assert_eq!(a.offset(5), Ok(1));
+    assert_eq!(a.offset(64), Err(2));
+    assert_eq!(a.offset(68), Ok(2));
+    assert_eq!(a.offset(128), Err(3));
+    assert_eq!(a.offset(200), Ok(4));
+    assert_eq!(a.offset(254), Err(5));
+    assert_eq!(a.offset(255), Ok(5));
+
+    // Check every key against a scan of the set bits
+    for key in 0..=255 {
+        let before = a.iter().filter(|k| *k < key).count() as u8;
+        let expected = match a.get(key) {
+            true => Ok(before),
+            false => Err(before),
+        };
+        assert_eq!(a.offset(key), expected);
+    }
+
+    // All set - the last key has 255 before it
+    a.set_all();
+    assert_eq!(a.offset(255), Ok(255));
+    a.clear_all();
+    assert_eq!(a.offset(255), Err(0));
+}
+
+
+#[test]
+fn iter_range() {
+    let mut v = BitVec256::new();
+    let input_bits = vec![0_u8, 28, 63, 64, 129, 255];
+    for b in &input_bits {
+        v.set(*b);
+    }
+
+    for (start, end) in &[(0_u8, 255_u8), (1, 63), (63, 64), (65, 254), 
+            (255, 255), (30, 20)] {
+        let expected: Vec<u8> = input_bits.iter().cloned()
+            .filter(|b| b >= start && b <= end).collect();
+        let output: Vec<u8> = v.iter_range(*start, *end).collect();
+        assert_eq!(expected, output);
+    }
 }
 
+#[test]
+fn iter_rev() {
+    let mut v = BitVec256::new();
+    let input_bits = vec![0_u8, 28, 63, 64, 129, 255];
+    for b in &input_bits {
+        v.set(*b);
+    }
+
+    let expected: Vec<u8> = input_bits.iter().rev().cloned().collect();
+    let output: Vec<u8> = v.iter().rev().collect();
+    assert_eq!(expected, output);
+
+    let output: Vec<u8> = v.iter_range(1, 128).rev().collect();
+    assert_eq!(vec![64_u8, 63, 28], output);
+
+    // Front and back meet in the middle
+    let mut iter = v.iter();
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(255));
+    assert_eq!(iter.next_back(), Some(129));
+    assert_eq!(iter.next(), Some(28));
+    assert_eq!(iter.next(), Some(63));
+    assert_eq!(iter.next(), Some(64));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn count_ones() {
+    let mut v = BitVec256::new();
+    assert_eq!(v.count_ones(), 0);
+    for b in &[0_u8, 63, 64, 200, 255] {
+        v.set(*b);
+    }
+    assert_eq!(v.count_ones(), 5);
+    v.set_all();
+    assert_eq!(v.count_ones(), 256);
+}
+
+#[test]
+fn next_and_prev() {
+    let mut v = BitVec256::new();
+    for b in &[5_u8, 63, 64, 200] {
+        v.set(*b);
+    }
+    assert_eq!(v.next_set(0), Some(5));
+    assert_eq!(v.next_set(6), Some(63));
+    assert_eq!(v.next_set(65), Some(200));
+    assert_eq!(v.next_set(201), None);
+    assert_eq!(v.prev_set(255), Some(200));
+    assert_eq!(v.prev_set(63), Some(63));
+    assert_eq!(v.prev_set(4), None);
+
+    v.set_all();
+    v.clear(70);
+    v.clear(130);
+    assert_eq!(v.next_clear(0), Some(70));
+    assert_eq!(v.next_clear(71), Some(130));
+    assert_eq!(v.next_clear(131), None);
+    assert_eq!(v.prev_clear(255), Some(130));
+    assert_eq!(v.prev_clear(129), Some(70));
+    assert_eq!(v.prev_clear(69), None);
+}
+
+#[test]
+fn eq_and_ord() {
+    let mut a = BitVec256::new();
+    let mut b = BitVec256::new();
+    assert_eq!(a, b);
+
+    a.set(5);
+    b.set(200);
+    assert_ne!(a, b);
+    assert!(a < b);
+    b.set(5);
+    assert!(a < b);
+    a.set(201);
+    assert!(a > b);
+    a.clear(201);
+    a.set(200);
+    assert_eq!(a, b);
+    assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+}
+
+fn from_bits(bits: &[u8]) -> BitVec256 {
+    let mut v = BitVec256::new();
+    for b in bits {
+        v.set(*b);
+    }
+    v
+}
+
+#[test]
+fn xor_sub_not() {
+    let a = from_bits(&[1, 64, 130, 255]);
+    let b = from_bits(&[1, 65, 130]);
+    assert_eq!(&a ^ &b, from_bits(&[64, 65, 255]));
+    assert_eq!(&a - &b, from_bits(&[64, 255]));
+    assert_eq!(&b - &a, from_bits(&[65]));
+
+    let not_a = !&a;
+    assert_eq!(not_a.count_ones(), 252);
+    assert!(!not_a[64] && not_a[63]);
+    assert_eq!(!not_a, a);
+
+    // Assigning forms match the plain operators
+    let mut c = a.clone();
+    c &= &b;
+    assert_eq!(c, &a & &b);
+    let mut c = a.clone();
+    c |= &b;
+    assert_eq!(c, &a | &b);
+    let mut c = a.clone();
+    c ^= b.clone();
+    assert_eq!(c, &a ^ &b);
+    let mut c = a.clone();
+    c -= b;
+    assert_eq!(c, from_bits(&[64, 255]));
+}
+
+#[test]
+fn shifts() {
+    let a = from_bits(&[0, 5, 63, 64, 200, 255]);
+    assert_eq!(&a << 0, a);
+    assert_eq!(&a << 1, from_bits(&[1, 6, 64, 65, 201]));
+    assert_eq!(&a << 64, from_bits(&[64, 69, 127, 128]));
+    assert_eq!(&a << 70, from_bits(&[70, 75, 133, 134]));
+    assert_eq!(&a << 255, from_bits(&[255]));
+    assert!((&a << 256).is_empty());
+
+    assert_eq!(&a >> 0, a);
+    assert_eq!(&a >> 1, from_bits(&[4, 62, 63, 199, 254]));
+    assert_eq!(&a >> 64, from_bits(&[0, 136, 191]));
+    assert_eq!(&a >> 70, from_bits(&[130, 185]));
+    assert_eq!(&a >> 255, from_bits(&[0]));
+    assert!((&a >> u32::MAX).is_empty());
+
+    let mut b = a.clone();
+    b <<= 3;
+    b >>= 3;
+    assert_eq!(b, from_bits(&[0, 5, 63, 64, 200]));
+}
+
+#[test]
+fn first_last_rank_select() {
+    let empty = BitVec256::new();
+    assert_eq!(empty.first_set(), None);
+    assert_eq!(empty.last_set(), None);
+    assert_eq!(empty.select(0), None);
+    assert_eq!(empty.rank(255), 0);
+
+    let bits = [3_u8, 64, 65, 129, 254];
+    let a = from_bits(&bits);
+    assert_eq!(a.first_set(), Some(3));
+    assert_eq!(a.last_set(), Some(254));
+    assert_eq!(a.count_ones(), 5);
+    for (k, bitno) in bits.iter().enumerate() {
+        assert_eq!(a.select(k as u8), Some(*bitno));
+        assert_eq!(a.rank(*bitno), k as u8);
+    }
+    assert_eq!(a.select(5), None);
+    assert_eq!(a.rank(0), 0);
+    assert_eq!(a.rank(255), 5);
+
+    let mut full = BitVec256::new();
+    full.set_all();
+    assert_eq!(full.select(255), Some(255));
+    assert_eq!(full.rank(255), 255);
+}
+
+#[test]
+fn from_arrays() {
+    let a = BitVec256::from([1, 0, 1 << 63, 2]);
+    assert_eq!(a, from_bits(&[0, 191, 193]));
+
+    let mut bytes = [0_u8; 32];
+    bytes[0] = 0b_0000_0101;
+    bytes[8] = 0x80;
+    bytes[31] = 0x80;
+    assert_eq!(BitVec256::from(bytes), from_bits(&[0, 2, 71, 255]));
+}
@@ -0,0 +1,160 @@
+use crate::{Node,BitFooError};
+use std::io::{self,Read,Write};
+
+// Serialized format, version 1. All integers are little endian.
+//
+//   magic     4 bytes   "BFOO"
+//   version   u8        FORMAT_VERSION
+//   level     u8        Level of the root node (1-8)
+//   root      node      Root node, as below
+//   checksum  u32       CRC-32 (IEEE) of all preceding bytes
+//
+// Each node mirrors its KeyIndex and Content:
+//
+//   nodes     4 x u64   KeyIndex.nodes words, lowest key first
+//   runs      4 x u64   KeyIndex.runs words
+//   content   level 1:  one leaf u64 per key set in nodes
+//             level 2+: one child node per key set in nodes
+//
+// Children are written in key order and their level is implied
+// by the parent. The format is canonical: nodes and runs are
+// disjoint, and empty or full children never appear.
+const MAGIC: &[u8;4] = b"BFOO";
+const FORMAT_VERSION: u8 = 1;
+const MAX_ROOT_LEVEL: u8 = 8;
+
+// Write the tree under the root node
+pub (crate) fn write_tree<W: Write>(root: &Node, writer: W) 
+    -> Result<(), BitFooError> 
+{
+    let mut writer = CrcWriter::new(writer);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[FORMAT_VERSION, root.level()])?;
+    root.write_to(&mut writer)?;
+
+    let checksum = writer.crc.finish();
+    writer.inner.write_all(&checksum.to_le_bytes())?;
+    writer.inner.flush()?;
+    Ok(())
+}
+
+// Read a tree, returning the root node
+pub (crate) fn read_tree<R: Read>(reader: R) -> Result<Node, BitFooError> {
+    let mut reader = CrcReader::new(reader);
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(BitFooError::BadMagic);
+    }
+
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let [version, level] = header;
+    if version != FORMAT_VERSION {
+        return Err(BitFooError::UnsupportedVersion(version));
+    }
+    if !(1..=MAX_ROOT_LEVEL).contains(&level) {
+        return Err(BitFooError::Corrupt("root level out of range"));
+    }
+    let root = Node::read_from(&mut reader, level)?;
+
+    // Checksum isn't part of the checksummed bytes
+    let computed = reader.crc.finish();
+    let mut checksum = [0; 4];
+    reader.inner.read_exact(&mut checksum)?;
+    if u32::from_le_bytes(checksum) != computed {
+        return Err(BitFooError::ChecksumMismatch);
+    }
+    Ok(root)
+}
+
+// Running CRC-32 using the IEEE polynomial (as used by zlib)
+pub (crate) struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    pub (crate) fn new() -> Self {
+        Crc32 { state: u32::MAX }
+    }
+
+    pub (crate) fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            let index = (self.state ^ *byte as u32) & 0xff;
+            self.state = CRC_TABLE[index as usize] ^ (self.state >> 8);
+        }
+    }
+
+    pub (crate) fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+const CRC_TABLE: [u32;256] = crc_table();
+
+const fn crc_table() -> [u32;256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xedb8_8320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+// Writer which checksums everything written through it
+struct CrcWriter<W> {
+    inner: W,
+    crc: Crc32,
+}
+
+impl<W: Write> CrcWriter<W> {
+    fn new(inner: W) -> Self {
+        CrcWriter { inner, crc: Crc32::new() }
+    }
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.crc.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Reader which checksums everything read through it
+struct CrcReader<R> {
+    inner: R,
+    crc: Crc32,
+}
+
+impl<R: Read> CrcReader<R> {
+    fn new(inner: R) -> Self {
+        CrcReader { inner, crc: Crc32::new() }
+    }
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.crc.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/codec_test.rs"]
+mod tests;
@@ -0,0 +1,78 @@
+use std::{error,fmt,io};
+
+// Errors returned by the fallible BitFoo operations
+#[derive(Debug)]
+pub enum BitFooError {
+    Io(io::Error),              // Failure reading or writing the stream
+    BadMagic,                   // Input is not a serialized BitFooVec
+    UnsupportedVersion(u8),     // Format version we can't read
+    ChecksumMismatch,           // Input was damaged
+    Corrupt(&'static str),      // Input breaks a tree invariant
+    OutOfRange(u64),            // Bit can't be held by the target format
+    TooLarge(u64),              // Output needs more than this many containers
+    Unsorted(u64),              // Bit came after a higher one
+    BadLevel(u8),               // No node or address at this level
+    BadKey {                    // Key too large for its level
+        level: u8,
+        key: u8,
+    },
+    Invalid {                   // Tree breaks an invariant
+        level: u8,              // Level of the bad node
+        bitno: u64,             // First bit covered by the bad node
+        reason: &'static str,
+    },
+    Parse {                     // Bad text, at this byte offset
+        offset: usize,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for BitFooError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitFooError::Io(err) => write!(f, "I/O error: {}", err),
+            BitFooError::BadMagic => write!(f, "bad magic header"),
+            BitFooError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            },
+            BitFooError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            BitFooError::Corrupt(reason) => write!(f, "corrupt input: {}", reason),
+            BitFooError::OutOfRange(bitno) => {
+                write!(f, "bit {} is out of range for the format", bitno)
+            },
+            BitFooError::TooLarge(limit) => {
+                write!(f, "output needs more than {} containers", limit)
+            },
+            BitFooError::Unsorted(bitno) => {
+                write!(f, "bit {} is out of order", bitno)
+            },
+            BitFooError::BadLevel(level) => {
+                write!(f, "level {} is out of range", level)
+            },
+            BitFooError::BadKey { level, key } => {
+                write!(f, "key {} is out of range for level {}", key, level)
+            },
+            BitFooError::Invalid { level, bitno, reason } => {
+                write!(f, "invalid level {} node at bit {}: {}", level, bitno, reason)
+            },
+            BitFooError::Parse { offset, reason } => {
+                write!(f, "parse error at offset {}: {}", offset, reason)
+            },
+        }
+    }
+}
+
+impl error::Error for BitFooError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BitFooError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BitFooError {
+    fn from(err: io::Error) -> Self {
+        BitFooError::Io(err)
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{Addr,BitFooError,BitVec256,Node};
+use crate::bitfoovec::bounds;
+use std::convert::TryInto;
+use std::fmt;
+use std::ops::{RangeBounds,RangeInclusive};
+
+// Frozen layout, version 1. The tree is laid out depth first so
+// it can be read in place (e.g. from an mmap) without any
+// deserializing. All integers are little endian.
+//
+//   magic     4 bytes   "BFRZ"
+//   version   u8        FROZEN_VERSION
+//   level     u8        Level of the root node (1-8)
+//   padding   2 bytes
+//   count     u128      Number of bits set
+//   root      node      Root node, as below
+//
+// Each node is its KeyIndex then its content:
+//
+//   nodes     4 x u64   KeyIndex.nodes words, lowest key first
+//   runs      4 x u64   KeyIndex.runs words
+//   content   level 1:  one leaf u64 per key set in nodes
+//             level 2+: one { before u64, offset u64 } entry per
+//                       key set in nodes, then a u64 total. Before
+//                       is the number of bits set in the children
+//                       ahead of this one, offset is the position of
+//                       the child node in the buffer and total is the
+//                       number of bits set in all of the children.
+//                       The children follow in key order.
+//
+// Lookups walk down from the root, so get() and rank() are
+// O(levels), and iteration keeps its position in the buffer.
+const MAGIC: &[u8;4] = b"BFRZ";
+const FROZEN_VERSION: u8 = 1;
+const MAX_ROOT_LEVEL: u8 = 8;
+const HEADER_LEN: usize = 24;
+const INDEX_LEN: usize = 64;
+
+// Read-only bit vector over a buffer written by freeze()
+#[derive(Clone,Copy)]
+pub struct FrozenBitFooVec<'a> {
+    bytes: &'a [u8],
+    level: u8,
+}
+
+// Public interface
+impl<'a> FrozenBitFooVec<'a> {
+    // Wrap a frozen buffer. The whole tree is checked once here,
+    // so later queries can't run off the end of the buffer. The
+    // check is O(n) and reads every byte, so it faults in every
+    // page of an mmap. Use new_unchecked() to skip it.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, BitFooError> {
+        let frozen = Self::new_unchecked(bytes)?;
+        let (end, count) = frozen.check_node(HEADER_LEN, frozen.level)?;
+        if end != bytes.len() {
+            return Err(BitFooError::Corrupt("trailing bytes after frozen tree"));
+        }
+        if count != frozen.count() {
+            return Err(BitFooError::Corrupt("count mismatch"));
+        }
+        Ok(frozen)
+    }
+
+    // Wrap a frozen buffer, checking only the header. This is
+    // O(1) and leaves the tree untouched until it is queried,
+    // which suits an mmap of a buffer we froze ourselves. A bad
+    // tree can't cause undefined behaviour, but queries on it
+    // may panic or give wrong answers.
+    pub fn new_unchecked(bytes: &'a [u8]) -> Result<Self, BitFooError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(BitFooError::Corrupt("frozen buffer too short"));
+        }
+        if &bytes[0..4] != MAGIC {
+            return Err(BitFooError::BadMagic);
+        }
+        if bytes[4] != FROZEN_VERSION {
+            return Err(BitFooError::UnsupportedVersion(bytes[4]));
+        }
+
+        let level = bytes[5];
+        if !(1..=MAX_ROOT_LEVEL).contains(&level) {
+            return Err(BitFooError::Corrupt("root level out of range"));
+        }
+        if bytes[6..8] != [0, 0] {
+            return Err(BitFooError::Corrupt("header padding not zero"));
+        }
+        Ok(FrozenBitFooVec { bytes, level })
+    }
+
+    // Return the level of the root node
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    // Return the underlying buffer
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    // Return state of this bit
+    pub fn get(&self, bitno: u64) -> bool {
+        if bitno > self.max_bitno() {
+            return false;
+        }
+
+        let mut pos = HEADER_LEN;
+        let mut level = self.level;
+        loop {
+            let key = Addr::new(bitno).key(level);
+            let (nodes, runs) = self.index(pos);
+            if runs.get(key) {
+                return true;
+            }
+            if !nodes.get(key) {
+                return false;
+            }
+
+            let offset = key_offset(&nodes, key);
+            if level == 1 {
+                let word = self.word(leaf(pos, offset));
+                return word & (1 << (bitno % 64)) != 0;
+            }
+            pos = self.word(entry(pos, offset) + 8) as usize;
+            level -= 1;
+        }
+    }
+
+    // Return the number of bits set
+    pub fn count(&self) -> u128 {
+        u128::from_le_bytes(self.bytes[8..HEADER_LEN].try_into().unwrap())
+    }
+
+    // Return the number of bits set within the range
+    pub fn count_range<R: RangeBounds<u64>>(&self, range: R) -> u128 {
+        match bounds(&range) {
+            Some((start, end)) if start <= self.max_bitno() => {
+                let end = end.min(self.max_bitno());
+                let below_end = self.rank(end) as u128 + self.get(end) as u128;
+                below_end - self.rank(start) as u128
+            },
+            _ => 0,
+        }
+    }
+
+    // Return the number of bits set below bitno
+    pub fn rank(&self, bitno: u64) -> u64 {
+        if bitno > self.max_bitno() {
+            return self.count() as u64;
+        }
+
+        let mut rank: u64 = 0;
+        let mut pos = HEADER_LEN;
+        let mut level = self.level;
+        loop {
+            // Whole runs and child nodes before our key
+            let key = Addr::new(bitno).key(level);
+            let span = Addr::cardinality(level) + 1;
+            let (nodes, runs) = self.index(pos);
+            let offset = key_offset(&nodes, key);
+            rank += key_offset(&runs, key) as u64 * span;
+
+            if level == 1 {
+                for i in 0..offset {
+                    rank += self.word(leaf(pos, i)).count_ones() as u64;
+                }
+                if runs.get(key) {
+                    rank += bitno % 64;
+                }
+                else if nodes.get(key) {
+                    let mask = (1_u64 << (bitno % 64)) - 1;
+                    rank += (self.word(leaf(pos, offset)) & mask).count_ones() as u64;
+                }
+                return rank;
+            }
+
+            // Before for our key, or the total past the last child
+            rank += self.word(entry(pos, offset));
+            if runs.get(key) {
+                return rank + (bitno & (span - 1));
+            }
+            if !nodes.get(key) {
+                return rank;
+            }
+            pos = self.word(entry(pos, offset) + 8) as usize;
+            level -= 1;
+        }
+    }
+
+    // Return the first set bit at or after bitno
+    pub fn next_set(&self, bitno: u64) -> Option<u64> {
+        match self.seek(bitno) {
+            Segment::Run(run) => Some(*run.start()),
+            Segment::Bits(base, bits) => Some(base + bits.trailing_zeros() as u64),
+            Segment::End => None,
+        }
+    }
+
+    // Return an iterator over all set bits
+    pub fn iter(&self) -> FrozenIterator<'a> {
+        self.range(..)
+    }
+
+    // Return an iterator over the set bits within the range
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> FrozenIterator<'a> {
+        let (from, end) = match bounds(&range) {
+            Some((start, end)) => (Some(start), end),
+            None => (None, 0),
+        };
+        FrozenIterator {
+            frozen: *self,
+            from,
+            end,
+            segment: Segment::End,
+        }
+    }
+}
+
+// Private helpers
+impl<'a> FrozenBitFooVec<'a> {
+    // Highest bitno that can be held by the root node
+    fn max_bitno(&self) -> u64 {
+        Addr::cardinality(self.level + 1)
+    }
+
+    fn word(&self, pos: usize) -> u64 {
+        u64::from_le_bytes(self.bytes[pos..pos + 8].try_into().unwrap())
+    }
+
+    // Return the nodes and runs of the node at pos
+    fn index(&self, pos: usize) -> (BitVec256, BitVec256) {
+        let mut nodes = [0; 4];
+        let mut runs = [0; 4];
+        for i in 0..4 {
+            nodes[i] = self.word(pos + 8 * i);
+            runs[i] = self.word(pos + 32 + 8 * i);
+        }
+        (BitVec256::from_raw_data(nodes), BitVec256::from_raw_data(runs))
+    }
+
+    // Return the first leaf word or run holding a set bit at or
+    // after bitno. End is returned if there are none.
+    fn seek(&self, bitno: u64) -> Segment {
+        if bitno > self.max_bitno() {
+            return Segment::End;
+        }
+        self.seek_node(HEADER_LEN, self.level, 0, bitno).unwrap_or(Segment::End)
+    }
+
+    // As above within the node at pos, which starts at base
+    fn seek_node(&self, pos: usize, level: u8, base: u64, bitno: u64) -> Option<Segment> {
+        let span = Addr::cardinality(level) + 1;
+        let (nodes, runs) = self.index(pos);
+        let keys = &nodes | &runs;
+        let mut key = keys.next_set(Addr::new(bitno.max(base)).key(level))?;
+
+        loop {
+            let child_base = base + key as u64 * span;
+            let first = bitno.max(child_base);
+            let offset = key_offset(&nodes, key);
+
+            if runs.get(key) {
+                return Some(Segment::Run(first..=child_base + (span - 1)));
+            }
+            else if level == 1 {
+                let bits = self.word(leaf(pos, offset)) & (u64::MAX << (first % 64));
+                if bits != 0 {
+                    return Some(Segment::Bits(child_base, bits));
+                }
+            }
+            else {
+                let child_pos = self.word(entry(pos, offset) + 8) as usize;
+                let found = self.seek_node(child_pos, level - 1, child_base, first);
+                if found.is_some() {
+                    return found;
+                }
+            }
+
+            key = keys.next_set(key.checked_add(1)?)?;
+        }
+    }
+
+    // Check the node at pos, returning the position after it
+    // and its count
+    fn check_node(&self, pos: usize, level: u8) -> Result<(usize, u128), BitFooError> {
+        let index_end = pos + INDEX_LEN;
+        if index_end > self.bytes.len() {
+            return Err(BitFooError::Corrupt("frozen node past end of buffer"));
+        }
+
+        let (nodes, runs) = self.index(pos);
+        if !(&nodes & &runs).is_empty() {
+            return Err(BitFooError::Corrupt("key is both a node and a run"));
+        }
+        let max_key = Addr::new(u64::MAX).key(level);
+        if max_key < u8::MAX && (&nodes | &runs).next_set(max_key + 1).is_some() {
+            return Err(BitFooError::Corrupt("key out of range for level"));
+        }
+
+        let span = Addr::cardinality(level) as u128 + 1;
+        let children = nodes.count_ones() as usize;
+        let mut count = runs.count_ones() as u128 * span;
+
+        if level == 1 {
+            let end = leaf(pos, children);
+            if end > self.bytes.len() {
+                return Err(BitFooError::Corrupt("frozen node past end of buffer"));
+            }
+            for i in 0..children {
+                match self.word(leaf(pos, i)) {
+                    0 | u64::MAX => {
+                        return Err(BitFooError::Corrupt("leaf word is empty or full"));
+                    },
+                    word => count += word.count_ones() as u128,
+                }
+            }
+            return Ok((end, count));
+        }
+
+        // Children must follow the table in order, so each byte
+        // is checked exactly once
+        let mut end = entry(pos, children) + 8;
+        if end > self.bytes.len() {
+            return Err(BitFooError::Corrupt("frozen node past end of buffer"));
+        }
+
+        let mut before: u128 = 0;
+        for i in 0..children {
+            if self.word(entry(pos, i)) as u128 != before {
+                return Err(BitFooError::Corrupt("child rank mismatch"));
+            }
+            if self.word(entry(pos, i) + 8) != end as u64 {
+                return Err(BitFooError::Corrupt("child offset out of place"));
+            }
+
+            let (child_end, child_count) = self.check_node(end, level - 1)?;
+            if child_count == 0 || child_count == span {
+                return Err(BitFooError::Corrupt("child node is empty or full"));
+            }
+            end = child_end;
+            before += child_count;
+        }
+
+        if self.word(entry(pos, children)) as u128 != before {
+            return Err(BitFooError::Corrupt("child total mismatch"));
+        }
+        Ok((end, count + before))
+    }
+}
+
+impl<'a> fmt::Debug for FrozenBitFooVec<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FrozenBitFooVec {{ level: {}, count: {}, len: {} }}",
+               self.level, self.count(), self.bytes.len())
+    }
+}
+
+impl<'a> IntoIterator for &FrozenBitFooVec<'a> {
+    type Item = u64;
+    type IntoIter = FrozenIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Write the header then the tree
+pub (crate) fn freeze(root: &Node) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + INDEX_LEN);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[FROZEN_VERSION, root.level(), 0, 0]);
+    out.extend_from_slice(&[0; 16]);
+
+    let count = root.freeze_to(&mut out);
+    out[8..HEADER_LEN].copy_from_slice(&count.to_le_bytes());
+    out
+}
+
+// Number of keys set before key
+fn key_offset(vec: &BitVec256, key: u8) -> usize {
+    match vec.offset(key) {
+        Ok(offset) | Err(offset) => offset as usize,
+    }
+}
+
+// Position of a leaf word in a level 1 node
+fn leaf(pos: usize, offset: usize) -> usize {
+    pos + INDEX_LEN + 8 * offset
+}
+
+// Position of a child table entry in a level 2+ node
+fn entry(pos: usize, offset: usize) -> usize {
+    pos + INDEX_LEN + 16 * offset
+}
+
+// Part of the tree holding the next set bits
+enum Segment {
+    End,
+    Run(RangeInclusive<u64>),
+    Bits(u64, u64),     // Start bit and remaining bits of a leaf word
+}
+
+// Iterator over the set bits of a frozen vector. This holds a leaf
+// word or run at a time, and seeks from the root for the next one.
+pub struct FrozenIterator<'a> {
+    frozen: FrozenBitFooVec<'a>,
+    from: Option<u64>,  // Where to seek the next segment from
+    end: u64,           // Last bitno to return
+    segment: Segment,
+}
+
+impl<'a> Iterator for FrozenIterator<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let bitno = match &mut self.segment {
+                Segment::Run(run) => run.next(),
+                Segment::Bits(base, bits) if *bits != 0 => {
+                    let offset = bits.trailing_zeros() as u64;
+                    *bits &= *bits - 1;
+                    Some(*base + offset)
+                },
+                _ => None,
+            };
+
+            match bitno {
+                Some(bitno) if bitno <= self.end => return Some(bitno),
+                Some(_bitno) => {
+                    // Past the end of the range
+                    self.from = None;
+                    self.segment = Segment::End;
+                    return None;
+                },
+                None => {
+                    // Seek from just past the segment we finished
+                    let from = self.from.filter(|from| *from <= self.end)?;
+                    self.segment = self.frozen.seek(from);
+                    self.from = match &self.segment {
+                        Segment::Run(run) => run.end().checked_add(1),
+                        Segment::Bits(base, _bits) => base.checked_add(64),
+                        Segment::End => return None,
+                    };
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/frozen_test.rs"]
+mod tests;
@@ -73,7 +73,7 @@ impl KeyIndex {
     pub fn search(&self, addr: &Addr) -> KeyState {
         let key = self.key(addr);
         if self.runs.get(key) {
-            return KeyState::Run(key);
+            KeyState::Run(key)
         }
         else {
             match self.nodes.offset(key) {
@@ -127,28 +127,52 @@ impl KeyIndex {
     }
 
     // Return an iterator
-    pub fn iter(&self) -> KeyIndexIterator {
-        let mut node_iter = self.nodes.iter();
-        let mut run_iter = self.runs.iter();
+    pub fn iter(&self) -> KeyIndexIterator<'_> {
+        self.iter_range(0, u8::MAX)
+    }
+
+    // Return an iterator over the keys from start to end
+    // (inclusive). Node offsets start from the number of 
+    // nodes before the start key.
+    pub fn iter_range(&self, start: u8, end: u8) -> KeyIndexIterator<'_> {
+        let mut node_iter = self.nodes.iter_range(start, end);
+        let mut run_iter = self.runs.iter_range(start, end);
+        let node_offset = match self.nodes.offset(start) {
+            Ok(offset) => offset as usize,
+            Err(offset) => offset as usize,
+        };
+
+        // Back offset is one past the offset of the last node
+        let node_back_offset = match self.nodes.offset(end) {
+            Ok(offset) => offset as usize + 1,
+            Err(offset) => offset as usize,
+        };
 
         KeyIndexIterator {
             node_key: node_iter.next(),
-            node_offset: 0,
-            node_iter: node_iter,
+            node_offset,
+            node_back_key: node_iter.next_back(),
+            node_back_offset,
+            node_iter,
             run_key: run_iter.next(),
-            run_iter: run_iter,
+            run_back_key: run_iter.next_back(),
+            run_iter,
         }
     }
 }
 
 // Iterator over index returns the sequence
-// of KeyState's
+// of KeyState's. We keep the next key from the front
+// and back of both the node and run vectors.
 pub struct KeyIndexIterator<'a> {
     node_iter: BitVec256Iterator<'a>,
     node_key: Option<u8>,
     node_offset: usize,
+    node_back_key: Option<u8>,
+    node_back_offset: usize,
     run_iter: BitVec256Iterator<'a>,
     run_key: Option<u8>,
+    run_back_key: Option<u8>,
 }
 
 impl<'a> Iterator for KeyIndexIterator<'a> {
@@ -157,6 +181,15 @@ impl<'a> Iterator for KeyIndexIterator<'a> {
     fn next(&mut self) -> Option<KeyState> {
         let mut result = None;
 
+        // Once the front meets the back, only the
+        // back keys are left
+        if self.node_key.is_none() {
+            self.node_key = self.node_back_key.take();
+        }
+        if self.run_key.is_none() {
+            self.run_key = self.run_back_key.take();
+        }
+
         match (self.node_key, self.run_key) {
             (Some(node_key), Some(run_key)) => {
                 // Return the next of the node or run keys
@@ -187,6 +220,50 @@ impl<'a> Iterator for KeyIndexIterator<'a> {
     }
 }
 
+impl<'a> DoubleEndedIterator for KeyIndexIterator<'a> {
+    fn next_back(&mut self) -> Option<KeyState> {
+        let mut result = None;
+
+        // Once the back meets the front, only the
+        // front keys are left
+        if self.node_back_key.is_none() {
+            self.node_back_key = self.node_key.take();
+        }
+        if self.run_back_key.is_none() {
+            self.run_back_key = self.run_key.take();
+        }
+
+        match (self.node_back_key, self.run_back_key) {
+            (Some(node_key), Some(run_key)) => {
+                // Return the last of the node or run keys
+                if node_key > run_key {
+                    self.node_back_offset -= 1;
+                    result = Some(KeyState::Node(node_key, 
+                        self.node_back_offset));
+                    self.node_back_key = self.node_iter.next_back();
+                }
+                else {
+                    result = Some(KeyState::Run(run_key));
+                    self.run_back_key = self.run_iter.next_back();
+                }
+            },
+            (Some(node_key), None) => {
+                // Only a node key found
+                self.node_back_offset -= 1;
+                result = Some(KeyState::Node(node_key, self.node_back_offset));
+                self.node_back_key = self.node_iter.next_back();
+            },
+            (None, Some(run_key)) => {
+                // Only a run key found
+                result = Some(KeyState::Run(run_key));
+                self.run_back_key = self.run_iter.next_back();
+            }
+            _ => ()
+        }
+        result
+    }
+}
+
 // Clone interface
 impl Clone for KeyIndex {
     fn clone(&self) -> KeyIndex {
@@ -200,5 +277,8 @@ impl Clone for KeyIndex {
 
 
 #[cfg(test)]
+// Older tests spell out expected values long-hand
+#[allow(clippy::bool_assert_comparison, clippy::needless_late_init,
+    clippy::unnecessary_cast)]
 #[path = "./tests/key_test.rs"]
 mod tests;
@@ -1,6 +1,30 @@
+#[macro_use]
+mod macros;
+#[doc(hidden)]
+pub use macros::BitRange;
+
 mod bitfoovec;
 pub use bitfoovec::BitFooVec;
 
+mod stats;
+pub use stats::BitFooStats;
+
+mod allocator;
+pub use allocator::BitFooAllocator;
+
+mod error;
+pub use error::BitFooError;
+
+mod codec;
+mod roaring;
+mod text;
+
+mod frozen;
+pub use frozen::{FrozenBitFooVec,FrozenIterator};
+
+#[cfg(feature = "serde")]
+mod serde_impl;
+
 mod bitvec256;
 pub use bitvec256::{BitVec256,BitVec256Iterator};
 
@@ -8,7 +32,11 @@ mod addr;
 pub use addr::Addr;
 
 mod node; 
-pub use node::{Node};
+pub use node::{Node,NodeIterator,NodeRangeIterator};
 
 mod key; 
 pub use key::{KeyState,KeyIndex,KeyIndexIterator};
+
+#[cfg(test)]
+#[path = "./tests/helpers.rs"]
+mod test_helpers;
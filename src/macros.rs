@@ -0,0 +1,54 @@
+use std::ops::{Range,RangeInclusive,RangeFrom,RangeTo,RangeToInclusive,RangeFull};
+use crate::bitfoovec::bounds;
+
+// Build a BitFooVec from a list of bits and ranges, e.g.
+//
+//   bitfoovec![5, 98000, 42]
+//   bitfoovec![0..100, 5000, 1 << 40..=(1 << 40) + 10]
+//
+// The items are sorted and merged first, then each range is
+// written with one set_range() call.
+#[macro_export]
+macro_rules! bitfoovec {
+    () => {
+        $crate::BitFooVec::new()
+    };
+    ($($bits:expr),+ $(,)?) => {
+        $crate::BitFooVec::from_ranges(
+            [$($crate::BitRange::bit_range($bits)),+].iter().flatten().cloned()
+        )
+    };
+}
+
+// Converts a bitfoovec! item to an inclusive range, or None if 
+// it is empty. Only public so the macro can reach it.
+#[doc(hidden)]
+pub trait BitRange {
+    fn bit_range(self) -> Option<RangeInclusive<u64>>;
+}
+
+impl BitRange for u64 {
+    fn bit_range(self) -> Option<RangeInclusive<u64>> {
+        Some(self..=self)
+    }
+}
+
+// All of the std ranges over u64
+macro_rules! bit_range {
+    ($($range:ty),*) => {
+        $(
+            impl BitRange for $range {
+                fn bit_range(self) -> Option<RangeInclusive<u64>> {
+                    bounds(&self).map(|(start, end)| start..=end)
+                }
+            }
+        )*
+    };
+}
+
+bit_range!(Range<u64>, RangeInclusive<u64>, RangeFrom<u64>, RangeTo<u64>,
+    RangeToInclusive<u64>, RangeFull);
+
+#[cfg(test)]
+#[path = "./tests/macros_test.rs"]
+mod tests;
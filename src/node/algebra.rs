@@ -0,0 +1,231 @@
+use crate::BitVec256;
+use super::Node;
+use super::Content;
+
+// Set operations used to merge two trees
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum SetOp {
+    And,    // Intersection
+    Or,     // Union
+    Xor,    // Symmetric difference
+    Sub,    // Difference
+}
+
+impl SetOp {
+    // Apply the operation to a pair of leaf words
+    fn word(self, a: u64, b: u64) -> u64 {
+        match self {
+            SetOp::And => a & b,
+            SetOp::Or => a | b,
+            SetOp::Xor => a ^ b,
+            SetOp::Sub => a & !b,
+        }
+    }
+}
+
+// State of a child key in one of the trees being merged
+pub (super) enum Child<'a> {
+    Missing,
+    Run,
+    Bits(u64),
+    Node(&'a Node),
+}
+
+// Outcome for a child key in the merged tree
+enum Merged {
+    Missing,
+    Run,
+    Bits(u64),
+    Node(Node),
+}
+
+impl Node {
+    // Return true if no bits are set in this node
+    pub fn is_empty(&self) -> bool {
+        self.index.is_nodes_empty() && self.index.is_runs_empty()
+    }
+
+    // Merge with another node at the same level using the set
+    // operation. Runs short-circuit the merge (run | x = run,
+    // run & x = x) and runs are re-detected in the result.
+    pub (crate) fn merge(&self, other: &Node, op: SetOp) -> Node {
+        let mut result = Node::new(self.level());
+        let keys = &index_keys(self) | &index_keys(other);
+
+        // Node offsets for each tree, bumped as we pass each node
+        let mut self_offset = 0;
+        let mut other_offset = 0;
+
+        for key in keys.iter() {
+            let a = self.child(key, &mut self_offset);
+            let b = other.child(key, &mut other_offset);
+            let merged = match (&self.content, a, b) {
+                (Content::Bits(_), a, b) => {
+                    Merged::Bits(op.word(Self::child_word(a),
+                        Self::child_word(b)))
+                },
+                (Content::Nodes(_), a, b) => Self::merge_child(a, b, op),
+            };
+            result.push_child(key, merged);
+        }
+        result
+    }
+
+    // Return a node with all bits flipped within our span.
+    // NOTE: Only used for child nodes, which are at most level 7
+    // so always have a full 256 keys.
+    pub (crate) fn complement(&self) -> Node {
+        let mut result = Node::new(self.level());
+        let mut offset = 0;
+
+        for key in 0..=u8::MAX {
+            let merged = match self.child(key, &mut offset) {
+                Child::Missing => Merged::Run,
+                Child::Run => Merged::Missing,
+                Child::Bits(bits) => Merged::Bits(!bits),
+                Child::Node(node) => Merged::Node(node.complement()),
+            };
+            result.push_child(key, merged);
+        }
+        result
+    }
+}
+
+// Private helpers
+impl Node {
+    // Return the state of the child at this key. The offset
+    // must be the number of nodes before the key, and is
+    // incremented if the key is a node.
+    fn child(&self, key: u8, offset: &mut usize) -> Child<'_> {
+        if self.index.runs.get(key) {
+            Child::Run
+        }
+        else if self.index.nodes.get(key) {
+            *offset += 1;
+            match &self.content {
+                Content::Bits(vec) => Child::Bits(vec[*offset - 1]),
+                Content::Nodes(vec) => Child::Node(&vec[*offset - 1]),
+            }
+        }
+        else {
+            Child::Missing
+        }
+    }
+
+    // Return the state of the child at this key, looking up
+    // the node offset
+    pub (super) fn child_at(&self, key: u8) -> Child<'_> {
+        match self.index.nodes.offset(key) {
+            Ok(offset) => {
+                let mut offset = offset as usize;
+                self.child(key, &mut offset)
+            },
+            Err(_offset) => match self.index.runs.get(key) {
+                true => Child::Run,
+                false => Child::Missing,
+            },
+        }
+    }
+
+    // Return the leaf word for a child of a level 1 node
+    fn child_word(child: Child) -> u64 {
+        match child {
+            Child::Missing => 0,
+            Child::Run => u64::MAX,
+            Child::Bits(bits) => bits,
+            Child::Node(_node) => 0, // Not possible for level 1
+        }
+    }
+
+    // Merge a pair of child nodes
+    fn merge_child(a: Child, b: Child, op: SetOp) -> Merged {
+        match (op, a, b) {
+            // Missing on both sides is always missing
+            (_, Child::Missing, Child::Missing) => Merged::Missing,
+
+            // Union - a run on either side wins
+            (SetOp::Or, Child::Run, _) | (SetOp::Or, _, Child::Run) => {
+                Merged::Run
+            },
+            (SetOp::Or, Child::Node(x), Child::Missing) |
+            (SetOp::Or, Child::Missing, Child::Node(x)) => {
+                Merged::Node(x.clone())
+            },
+
+            // Intersection - a run gives the other side
+            (SetOp::And, Child::Run, Child::Run) => Merged::Run,
+            (SetOp::And, Child::Run, Child::Node(x)) |
+            (SetOp::And, Child::Node(x), Child::Run) => {
+                Merged::Node(x.clone())
+            },
+            (SetOp::And, _, Child::Missing) |
+            (SetOp::And, Child::Missing, _) => Merged::Missing,
+
+            // Symmetric difference - a run flips the other side
+            (SetOp::Xor, Child::Run, Child::Run) => Merged::Missing,
+            (SetOp::Xor, Child::Run, Child::Node(x)) |
+            (SetOp::Xor, Child::Node(x), Child::Run) => {
+                Merged::Node(x.complement())
+            },
+            (SetOp::Xor, Child::Run, Child::Missing) |
+            (SetOp::Xor, Child::Missing, Child::Run) => Merged::Run,
+            (SetOp::Xor, Child::Node(x), Child::Missing) |
+            (SetOp::Xor, Child::Missing, Child::Node(x)) => {
+                Merged::Node(x.clone())
+            },
+
+            // Difference - a run on the right removes everything
+            (SetOp::Sub, _, Child::Run) => Merged::Missing,
+            (SetOp::Sub, Child::Missing, _) => Merged::Missing,
+            (SetOp::Sub, Child::Run, Child::Node(x)) => {
+                Merged::Node(x.complement())
+            },
+            (SetOp::Sub, Child::Run, Child::Missing) => Merged::Run,
+            (SetOp::Sub, Child::Node(x), Child::Missing) => {
+                Merged::Node(x.clone())
+            },
+
+            // Both sides have nodes, so merge them
+            (op, Child::Node(x), Child::Node(y)) => {
+                Merged::Node(x.merge(y, op))
+            },
+
+            // Level 1 children are merged as words
+            (_, Child::Bits(_), _) | (_, _, Child::Bits(_)) => Merged::Missing,
+        }
+    }
+
+    // Append a merged child for a key greater than any
+    // key we hold, detecting runs and dropping empty children
+    fn push_child(&mut self, key: u8, merged: Merged) {
+        let merged = match merged {
+            Merged::Bits(0) => Merged::Missing,
+            Merged::Bits(u64::MAX) => Merged::Run,
+            Merged::Node(node) if node.is_empty() => Merged::Missing,
+            Merged::Node(node) if node.index.is_all_runs() => Merged::Run,
+            merged => merged,
+        };
+
+        match (merged, &mut self.content) {
+            (Merged::Run, _) => {
+                self.index.runs.set(key);
+            },
+            (Merged::Bits(bits), Content::Bits(vec)) => {
+                self.content_count += bits.count_ones() as u64;
+                vec.push(bits);
+                self.index.set_node_bit(key);
+            },
+            (Merged::Node(node), Content::Nodes(vec)) => {
+                self.content_count += node.count() as u64;
+                vec.push(node);
+                self.index.set_node_bit(key);
+            },
+            _ => (),
+        }
+    }
+}
+
+// Keys holding a node or run in the index
+fn index_keys(node: &Node) -> BitVec256 {
+    &node.index.nodes | &node.index.runs
+}
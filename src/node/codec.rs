@@ -0,0 +1,111 @@
+use crate::{BitVec256,BitFooError};
+use std::convert::TryInto;
+use std::io::{self,Read,Write};
+use super::Node;
+use super::Content;
+
+// Binary encoding of a node and its children. See crate::codec
+// for the layout.
+impl Node {
+    // Write this node then its content
+    pub (crate) fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(64);
+        write_bitvec(&mut buf, &self.index.nodes);
+        write_bitvec(&mut buf, &self.index.runs);
+
+        match &self.content {
+            Content::Bits(vec) => {
+                // Leaf words go out in the same write as the index
+                for word in vec {
+                    buf.extend_from_slice(&word.to_le_bytes());
+                }
+                writer.write_all(&buf)
+            },
+            Content::Nodes(vec) => {
+                writer.write_all(&buf)?;
+                for child in vec {
+                    child.write_to(writer)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    // Build a node at this level directly from the stream,
+    // checking the invariants the rest of the tree relies on
+    pub (crate) fn read_from<R: Read>(reader: &mut R, level: u8) 
+        -> Result<Node, BitFooError> 
+    {
+        let mut node = Node::new(level);
+        node.index.nodes = read_bitvec(reader)?;
+        node.index.runs = read_bitvec(reader)?;
+        node.check_index()?;
+
+        let count = node.index.nodes.count_ones() as usize;
+        match &mut node.content {
+            Content::Bits(vec) => {
+                let mut buf = vec![0; count * 8];
+                reader.read_exact(&mut buf)?;
+                vec.reserve_exact(count);
+
+                for bytes in buf.chunks_exact(8) {
+                    let word = u64::from_le_bytes(bytes.try_into().unwrap());
+                    if word == 0 || word == u64::MAX {
+                        return Err(BitFooError::Corrupt("leaf word is empty or full"));
+                    }
+                    vec.push(word);
+                }
+            },
+            Content::Nodes(vec) => {
+                vec.reserve_exact(count);
+                for _ in 0..count {
+                    let child = Node::read_from(reader, level - 1)?;
+                    if child.is_empty() || child.index.is_all_runs() {
+                        return Err(BitFooError::Corrupt("child node is empty or full"));
+                    }
+                    vec.push(child);
+                }
+            },
+        }
+        node.recount();
+        Ok(node)
+    }
+}
+
+// Private helpers
+impl Node {
+    // Nodes and runs must be disjoint and only use keys 
+    // within our span
+    fn check_index(&self) -> Result<(), BitFooError> {
+        let overlap = &self.index.nodes & &self.index.runs;
+        if !overlap.is_empty() {
+            return Err(BitFooError::Corrupt("key is both a node and a run"));
+        }
+
+        let keys = &self.index.nodes | &self.index.runs;
+        match self.max_key() {
+            u8::MAX => Ok(()),
+            max_key => match keys.next_set(max_key + 1) {
+                Some(_key) => Err(BitFooError::Corrupt("key out of range for level")),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+fn write_bitvec(buf: &mut Vec<u8>, vec: &BitVec256) {
+    for offset in 0..4 {
+        buf.extend_from_slice(&vec.raw_data(offset).to_le_bytes());
+    }
+}
+
+fn read_bitvec<R: Read>(reader: &mut R) -> io::Result<BitVec256> {
+    let mut buf = [0; 32];
+    reader.read_exact(&mut buf)?;
+
+    let mut data = [0; 4];
+    for (word, bytes) in data.iter_mut().zip(buf.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    Ok(BitVec256::from_raw_data(data))
+}
@@ -0,0 +1,46 @@
+use crate::Addr;
+use super::Node;
+use super::Content;
+
+// Writes the frozen layout read by FrozenBitFooVec. See
+// frozen.rs for the layout.
+impl Node {
+    // Append this node and its children, returning our count
+    pub (crate) fn freeze_to(&self, out: &mut Vec<u8>) -> u128 {
+        for offset in 0..4 {
+            out.extend_from_slice(&self.index.nodes.raw_data(offset).to_le_bytes());
+        }
+        for offset in 0..4 {
+            out.extend_from_slice(&self.index.runs.raw_data(offset).to_le_bytes());
+        }
+
+        let span = Addr::cardinality(self.level()) as u128 + 1;
+        let runs = self.index.runs.count_ones() as u128 * span;
+        match &self.content {
+            Content::Bits(vec) => {
+                for word in vec {
+                    out.extend_from_slice(&word.to_le_bytes());
+                }
+                runs + vec.iter().map(|word| word.count_ones() as u128).sum::<u128>()
+            },
+            Content::Nodes(vec) => {
+                // Fill in the child table as each child is written
+                let table = out.len();
+                out.resize(table + 16 * vec.len() + 8, 0);
+
+                let mut before: u128 = 0;
+                for (i, child) in vec.iter().enumerate() {
+                    let entry = table + 16 * i;
+                    let position = out.len() as u64;
+                    out[entry..entry + 8].copy_from_slice(&(before as u64).to_le_bytes());
+                    out[entry + 8..entry + 16].copy_from_slice(&position.to_le_bytes());
+                    before += child.freeze_to(out);
+                }
+
+                let entry = table + 16 * vec.len();
+                out[entry..entry + 8].copy_from_slice(&(before as u64).to_le_bytes());
+                runs + before
+            },
+        }
+    }
+}
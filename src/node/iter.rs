@@ -1,10 +1,11 @@
 use crate::{Addr,KeyState,KeyIndexIterator};
 use std::iter::Iterator;
+use std::ops::RangeInclusive;
 use super::Node;
 use super::Content;
 
 // Define iterators for the 3 types of content
-// we may run into. Bits/Node are Node Contents, 
+// we may run into. Bits/Node are Node Contents,
 // whereas Run is from a KeyIndex
 enum ChildIterator<'a> {
     End,
@@ -15,18 +16,14 @@ enum ChildIterator<'a> {
 
 // Iterator for run bits
 struct RunIterator {
-    curr_bit: u64,
-    end_bit: u64,
+    bits: RangeInclusive<u64>,
 }
 
 impl RunIterator {
-    fn new(parent_addr: &Addr, key: u8, level: u8) -> RunIterator {
-        let mut addr = parent_addr.clone();
-        addr.set(level, key);
-
+    fn new(start_bit: u64, end_bit: u64) -> RunIterator {
+        // NOTE: RangeInclusive handles a run ending at u64::MAX
         RunIterator {
-            curr_bit: addr.min_bitno(level), 
-            end_bit: addr.max_bitno(level),
+            bits: start_bit..=end_bit,
         }
     }
 }
@@ -35,35 +32,30 @@ impl Iterator for RunIterator {
     type Item = u64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.curr_bit <= self.end_bit {
-            let retval = self.curr_bit;
-            self.curr_bit += 1;
-            Some(retval)
-        }
-        else {
-            None
-        }
+        self.bits.next()
+    }
+}
+
+impl DoubleEndedIterator for RunIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.bits.next_back()
     }
 }
 
 // Iterator for raw level 0 bits
 struct BitsIterator {
-    start_bit: u64, 
-    bits: u64,
-    bitno: u64,
+    start_bit: u64,
+    bits: u64,      // Remaining bits - cleared as they are returned
 }
 
 impl BitsIterator {
-    fn new(parent_addr: &Addr, key: u8, bits: u64) -> BitsIterator {
-        // Level 1 is set to the current key
-        let mut addr = parent_addr.clone();
-        addr.set(1, key);
-        let start_bit = addr.min_bitno(0);
-
+    // Bits outside of first_bit..=last_bit are masked out
+    fn new(start_bit: u64, bits: u64, first_bit: u64, last_bit: u64) 
+        -> BitsIterator 
+    {
         BitsIterator {
-            start_bit, 
-            bits,
-            bitno: 0, // This is relative to the start of our bits
+            start_bit,
+            bits: bits & Node::word_mask(start_bit, first_bit, last_bit),
         }
     }
 }
@@ -72,85 +64,117 @@ impl Iterator for BitsIterator {
     type Item = u64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.bitno >= 63 {
+        if self.bits == 0 {
+            None
+        }
+        else {
+            // Return and then clear the lowest set bit
+            let offset = self.bits.trailing_zeros() as u64;
+            self.bits &= self.bits - 1;
+            Some(self.start_bit + offset)
+        }
+    }
+}
+
+impl DoubleEndedIterator for BitsIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.bits == 0 {
             None
         }
         else {
-            let word = self.bits >> self.bitno;
-            let offset = word.trailing_zeros() as u64;
-            let retval = self.bitno + offset;
-            self.bitno = retval + 1;
-            Some(retval + self.start_bit)
+            // Return and then clear the highest set bit
+            let offset = 63 - self.bits.leading_zeros() as u64;
+            self.bits &= !(1 << offset);
+            Some(self.start_bit + offset)
+        }
+    }
+}
+
+impl<'a> ChildIterator<'a> {
+    // Return next item from the front of the child iterator
+    fn next(&mut self) -> Option<u64> {
+        match self {
+            ChildIterator::Bits(iter) => iter.next(),
+            ChildIterator::Run(iter) => iter.next(),
+            ChildIterator::Node(iter) => iter.next(),
+            ChildIterator::End => None,
+        }
+    }
+
+    // Return next item from the back of the child iterator
+    fn next_back(&mut self) -> Option<u64> {
+        match self {
+            ChildIterator::Bits(iter) => iter.next_back(),
+            ChildIterator::Run(iter) => iter.next_back(),
+            ChildIterator::Node(iter) => iter.next_back(),
+            ChildIterator::End => None,
         }
     }
 }
 
 // Main iterator for a node
 pub struct NodeIterator<'a> {
-    addr: Addr,
+    addr: Addr,     // Seek position (higher keys give our position)
+    start: u64,     // First bitno to return
+    end: u64,       // Last bitno to return
     node: &'a Node,
     index_iter: KeyIndexIterator<'a>,
-    child_iter: ChildIterator<'a>,
+    child_iter: ChildIterator<'a>,  // Child at the front
+    back_iter: ChildIterator<'a>,   // Child at the back
 }
 
 impl<'a> NodeIterator<'a> {
-    pub fn new(node: &'a Node, addr: Addr) -> NodeIterator {
+    // Iterate over bits from the addr up to end (inclusive)
+    pub fn new(node: &'a Node, addr: Addr, end: u64) -> NodeIterator<'a> {
+        // Only visit the keys overlapping start..=end, so we
+        // skip whole subtrees outside of the range
+        let start = addr.bitno();
+        let (start_key, end_key) = node.key_range(&addr, end);
+
         NodeIterator {
-            addr: addr.clone(), 
+            addr,
+            start,
+            end,
             node,
-            index_iter: node.index.iter(), 
+            index_iter: node.index.iter_range(start_key, end_key),
             child_iter: ChildIterator::End,
+            back_iter: ChildIterator::End,
         }
     }
 
-    // Return next item from child iterator
-    fn next_from_child(&mut self) -> Option<u64> {
-        match &mut self.child_iter {
-            ChildIterator::Bits(iter) => iter.next(),
-            ChildIterator::Run(iter) => iter.next(),
-            ChildIterator::Node(iter) => iter.next(),
-            ChildIterator::End => None,
-        }
-    }
-
-    // Return the next child iterator
-    fn update_child_iterator(&mut self) {
-        self.child_iter = match self.index_iter.next() {
-            Some(KeyState::Node(key, offset)) => {
+    // Return the child iterator for the given key state
+    fn child_iterator(&self, key_state: KeyState) -> ChildIterator<'a> {
+        match key_state {
+            KeyState::Node(key, offset) => {
                 // Iterator for child node
+                let (min_bit, _max_bit) = self.node.child_span(&self.addr, key);
                 match &self.node.content {
                     Content::Bits(vec) => {
-                        println!("update_child_iter: Bits({}) \
-                            offset={} key={} addr={:?}", 
-                            self.node.level(), offset, key, self.addr);
                         let child_bits = vec[offset];
                         ChildIterator::Bits(
-                            BitsIterator::new(&self.addr, key, child_bits)
+                            BitsIterator::new(min_bit, child_bits, 
+                                self.start, self.end)
                         )
                     },
                     Content::Nodes(vec) => {
-                        println!("update_child_iter: Nodes({}) \
-                            offset={} key={} addr={:?}", 
-                            self.node.level(), offset, key, self.addr);
+                        // Only the first child seeks past its start
+                        let child_addr = Addr::new(self.start.max(min_bit));
                         let child_node = &vec[offset];
                         ChildIterator::Node(
-                            Box::new(
-                                // EYE - do we need to pass in the key/level?
-                                child_node.iter(self.addr.clone())
-                            )
+                            Box::new(child_node.iter_range(child_addr, self.end))
                         )
                     },
                 }
             },
-            Some(KeyState::Run(key)) => {
-                println!("update_child_iter: Run({}) key={} addr={:?}", 
-                    self.node.level(), key, self.addr);
+            KeyState::Run(key) => {
+                let (min_bit, max_bit) = self.node.child_span(&self.addr, key);
                 ChildIterator::Run(
-                    RunIterator::new(&self.addr, key, self.node.level())
+                    RunIterator::new(self.start.max(min_bit), 
+                        self.end.min(max_bit))
                 )
             },
-            _ => ChildIterator::End
-        };
+            KeyState::Missing(_key, _offset) => ChildIterator::End,
+        }
     }
 }
 
@@ -159,12 +183,42 @@ impl<'a> Iterator for NodeIterator<'a> {
     type Item = u64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut result = self.next_from_child();
-        if result == None {
-            // We've exhausted the current child iterator
-            self.update_child_iterator();
-            result = self.next_from_child();
+        loop {
+            if let Some(bitno) = self.child_iter.next() {
+                return Some(bitno);
+            }
+
+            match self.index_iter.next() {
+                Some(key_state) => {
+                    // Move on to the next child
+                    self.child_iter = self.child_iterator(key_state);
+                },
+                None => {
+                    // Finish whatever the back iterator has left
+                    return self.back_iter.next();
+                }
+            }
         }
-        result
     }
-}
\ No newline at end of file
+}
+
+impl<'a> DoubleEndedIterator for NodeIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(bitno) = self.back_iter.next_back() {
+                return Some(bitno);
+            }
+
+            match self.index_iter.next_back() {
+                Some(key_state) => {
+                    // Move on to the previous child
+                    self.back_iter = self.child_iterator(key_state);
+                },
+                None => {
+                    // Finish whatever the front iterator has left
+                    return self.child_iter.next_back();
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,26 @@
+#[allow(clippy::module_inception)]
 mod node;
 mod ops;
 mod iter;
+mod ranges;
+mod algebra;
+mod navigate;
+mod codec;
+mod freeze;
+mod predicates;
+mod stats;
+mod validate;
 
 pub use self::node::Node;
+pub use self::iter::NodeIterator;
+pub use self::ranges::NodeRangeIterator;
 use self::node::Content;
+pub (crate) use self::algebra::SetOp;
+pub (crate) use self::ranges::cmp_ranges;
 
 #[cfg(test)]
+// Older tests spell out expected values long-hand
+#[allow(clippy::bool_assert_comparison, clippy::erasing_op,
+    clippy::identity_op, clippy::mistyped_literal_suffixes,
+    clippy::unnecessary_cast, clippy::zero_prefixed_literal)]
 mod tests;
\ No newline at end of file
@@ -0,0 +1,156 @@
+use crate::{Addr,KeyState};
+use super::Node;
+use super::Content;
+
+// Successor and predecessor queries. These scan the KeyIndex
+// words at each level rather than iterating over bits.
+impl Node {
+    // Return the first set bit at or after this address
+    pub fn next_set(&self, addr: &Addr) -> Option<u64> {
+        let start = addr.bitno();
+        let keys = &self.index.nodes | &self.index.runs;
+        let mut key = keys.next_set(self.index.key(addr))?;
+
+        loop {
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let first = start.max(min_bit);
+            let child_addr = Addr::new(first);
+
+            let found = match self.index.search(&child_addr) {
+                KeyState::Run(_key) => Some(first),
+                KeyState::Node(_key, offset) => {
+                    match &self.content {
+                        Content::Bits(vec) => {
+                            let mask = Self::word_mask(min_bit, first, max_bit);
+                            match vec[offset] & mask {
+                                0 => None,
+                                bits => Some(min_bit + bits.trailing_zeros() as u64),
+                            }
+                        },
+                        Content::Nodes(vec) => vec[offset].next_set(&child_addr),
+                    }
+                },
+                KeyState::Missing(_key, _offset) => None,
+            };
+
+            if found.is_some() {
+                return found;
+            }
+            key = keys.next_set(key.checked_add(1)?)?;
+        }
+    }
+
+    // Return the last set bit at or before this address
+    pub fn prev_set(&self, addr: &Addr) -> Option<u64> {
+        let start = addr.bitno();
+        let keys = &self.index.nodes | &self.index.runs;
+        let mut key = keys.prev_set(self.index.key(addr))?;
+
+        loop {
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let last = start.min(max_bit);
+            let child_addr = Addr::new(last);
+
+            let found = match self.index.search(&child_addr) {
+                KeyState::Run(_key) => Some(last),
+                KeyState::Node(_key, offset) => {
+                    match &self.content {
+                        Content::Bits(vec) => {
+                            let mask = Self::word_mask(min_bit, min_bit, last);
+                            match vec[offset] & mask {
+                                0 => None,
+                                bits => Some(min_bit + 63 - bits.leading_zeros() as u64),
+                            }
+                        },
+                        Content::Nodes(vec) => vec[offset].prev_set(&child_addr),
+                    }
+                },
+                KeyState::Missing(_key, _offset) => None,
+            };
+
+            if found.is_some() {
+                return found;
+            }
+            key = keys.prev_set(key.checked_sub(1)?)?;
+        }
+    }
+
+    // Return the first clear bit at or after this address. A
+    // missing key is all 0's and a run is all 1's.
+    pub fn next_clear(&self, addr: &Addr) -> Option<u64> {
+        let start = addr.bitno();
+        let mut key = self.index.key(addr);
+
+        loop {
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let first = start.max(min_bit);
+            let child_addr = Addr::new(first);
+
+            let found = match self.index.search(&child_addr) {
+                KeyState::Run(_key) => None,
+                KeyState::Node(_key, offset) => {
+                    match &self.content {
+                        Content::Bits(vec) => {
+                            let mask = Self::word_mask(min_bit, first, max_bit);
+                            match !vec[offset] & mask {
+                                0 => None,
+                                bits => Some(min_bit + bits.trailing_zeros() as u64),
+                            }
+                        },
+                        Content::Nodes(vec) => vec[offset].next_clear(&child_addr),
+                    }
+                },
+                KeyState::Missing(_key, _offset) => Some(first),
+            };
+
+            if found.is_some() {
+                return found;
+            }
+
+            // Skip over any runs that follow
+            if key >= self.max_key() {
+                return None;
+            }
+            key = self.index.runs.next_clear(key + 1)?;
+            if key > self.max_key() {
+                return None;
+            }
+        }
+    }
+
+    // Return the last clear bit at or before this address
+    pub fn prev_clear(&self, addr: &Addr) -> Option<u64> {
+        let start = addr.bitno();
+        let mut key = self.index.key(addr);
+
+        loop {
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let last = start.min(max_bit);
+            let child_addr = Addr::new(last);
+
+            let found = match self.index.search(&child_addr) {
+                KeyState::Run(_key) => None,
+                KeyState::Node(_key, offset) => {
+                    match &self.content {
+                        Content::Bits(vec) => {
+                            let mask = Self::word_mask(min_bit, min_bit, last);
+                            match !vec[offset] & mask {
+                                0 => None,
+                                bits => Some(min_bit + 63 - bits.leading_zeros() as u64),
+                            }
+                        },
+                        Content::Nodes(vec) => vec[offset].prev_clear(&child_addr),
+                    }
+                },
+                KeyState::Missing(_key, _offset) => Some(last),
+            };
+
+            if found.is_some() {
+                return found;
+            }
+
+            // Skip over any runs that precede
+            key = self.index.runs.prev_clear(key.checked_sub(1)?)?;
+        }
+    }
+}
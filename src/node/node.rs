@@ -1,5 +1,8 @@
-use crate::{Addr,KeyIndex,KeyState};
+use crate::{Addr,KeyIndex,KeyState,BitFooError};
 use crate::node::iter::NodeIterator;
+use crate::node::ranges::{NodeRangeIterator,cmp_ranges};
+use std::cmp::Ordering;
+use std::hash::{Hash,Hasher};
 
 #[derive(Debug)]
 pub enum Content {
@@ -11,27 +14,32 @@ pub enum Content {
 pub struct Node {
     pub index: KeyIndex,          // Indexes content keys by vec offset
     pub (super) content: Content, // Contains vec of either u64 bits or Nodes
+    pub (super) content_count: u64, // Bits set in content (runs excluded)
 }
 
 // Public interface
 impl Node {
-    // Constructor
+    // Constructor. Panics if the level is outside 1-9.
     pub fn new(level: u8) -> Self {
+        Self::try_new(level).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Constructor returning an error if the level is outside 1-9
+    pub fn try_new(level: u8) -> Result<Self, BitFooError> {
         // Level 1 contains a list of 64-bit (2^6) chunks (which are level 0)
         // Level 2+ contains up to 256 (2^8) child nodes
         let content = match level {
             1 => Content::Bits(Vec::with_capacity(1)),
             2..=9 => Content::Nodes(Vec::with_capacity(1)),
-            _ => {
-                panic!("Nodes can only be constructed with levels 1-9");
-            }
+            _ => return Err(BitFooError::BadLevel(level)),
         };
                 
         // Contains child nodes 
-        Node {
+        Ok(Node {
             index: KeyIndex::new(level),
-            content
-        }
+            content,
+            content_count: 0,
+        })
     }
 
     // Return Our level
@@ -41,68 +49,456 @@ impl Node {
 
     // Set the bit corresponding to this address.
     pub fn set(&mut self, addr: &Addr) {
+        self.set_word(addr, 0x1 << addr.key(0));
+    }
+
+    // Set the given bits in the leaf word holding this address.
+    // The address only needs to be within the word.
+    pub (crate) fn set_word(&mut self, addr: &Addr, bits: u64) {
+        if bits == 0 {
+            return;
+        }
+
+        let count = &mut self.content_count;
         match &mut self.content {
             Content::Bits(vec) => {
-                Self::set_bits(&mut self.index, vec, addr);
+                Self::set_bits(&mut self.index, vec, count, addr, bits);
             },
             Content::Nodes(vec) => {
-                Self::set_nodes(&mut self.index, vec, addr);
+                Self::set_nodes(&mut self.index, vec, count, addr, bits);
             }
         }
     }
 
+    // Set the given bits in a leaf word at or after the last word
+    // we hold. Content is only pushed to the end, and a child that
+    // fills up becomes a run.
+    pub (crate) fn push_word(&mut self, addr: &Addr, bits: u64) {
+        if bits == 0 || self.index.is_run(addr) {
+            return;
+        }
+
+        let level = self.level();
+        match &mut self.content {
+            Content::Bits(vec) => {
+                // Take back the last word if we're adding to it
+                let old = match self.index.is_node(addr) {
+                    true => vec.pop().unwrap_or(0),
+                    false => 0,
+                };
+                let bits = old | bits;
+                self.content_count -= old.count_ones() as u64;
+                if bits == u64::MAX {
+                    self.index.run(addr);
+                }
+                else {
+                    self.content_count += bits.count_ones() as u64;
+                    vec.push(bits);
+                    self.index.set(addr);
+                }
+            },
+            Content::Nodes(vec) => {
+                let mut child = match self.index.is_node(addr) {
+                    true => vec.pop().unwrap_or_else(|| Node::new(level - 1)),
+                    false => Node::new(level - 1),
+                };
+                self.content_count -= child.count() as u64;
+                child.push_word(addr, bits);
+                if child.index.is_all_runs() {
+                    self.index.run(addr);
+                }
+                else {
+                    self.content_count += child.count() as u64;
+                    vec.push(child);
+                    self.index.set(addr);
+                }
+            },
+        }
+    }
+
     // Clear the bit corresponding to this address 
     pub fn clear(&mut self, addr: &Addr) {
+        let count = &mut self.content_count;
         match &mut self.content {
             Content::Bits(vec) => {
-                Self::clear_bits(&mut self.index, vec, addr);
+                Self::clear_bits(&mut self.index, vec, count, addr);
             },
             Content::Nodes(vec) => {
-                Self::clear_nodes(&mut self.index, vec, addr);
+                Self::clear_nodes(&mut self.index, vec, count, addr);
+            }
+        }
+    }
+
+    // Set all bits from the starting Addr up to the end bit number
+    // (inclusive). Children fully covered by the range become runs,
+    // so only the leaf words at the edges are written.
+    pub fn set_range(&mut self, addr: &Addr, end: u64) {
+        let start = addr.bitno();
+        let (start_key, end_key) = self.key_range(addr, end);
+
+        for key in start_key..=end_key {
+            // Part of the range held by this child
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let (first, last) = (start.max(min_bit), end.min(max_bit));
+            let child_addr = Addr::new(first);
+
+            if first == min_bit && last == max_bit {
+                // Whole child is set - replace with a run
+                if let KeyState::Node(_key, offset) = self.index.search(&child_addr) {
+                    self.remove_child(offset);
+                }
+                self.index.run(&child_addr);
+                continue;
+            }
+
+            match &mut self.content {
+                Content::Bits(vec) => {
+                    let mask = Self::word_mask(min_bit, first, last);
+                    Self::set_bits_range(&mut self.index, vec, &child_addr, mask);
+                },
+                Content::Nodes(vec) => {
+                    Self::set_nodes_range(&mut self.index, vec, &child_addr, last);
+                }
+            }
+        }
+        self.recount();
+    }
+
+    // Clear all bits from the starting Addr up to the end bit number
+    // (inclusive). Children fully covered by the range are removed
+    // and runs at the edges are split.
+    pub fn clear_range(&mut self, addr: &Addr, end: u64) {
+        let start = addr.bitno();
+        let (start_key, end_key) = self.key_range(addr, end);
+
+        for key in start_key..=end_key {
+            // Part of the range held by this child
+            let (min_bit, max_bit) = self.child_span(addr, key);
+            let (first, last) = (start.max(min_bit), end.min(max_bit));
+            let child_addr = Addr::new(first);
+
+            if first == min_bit && last == max_bit {
+                // Whole child is cleared - remove it
+                if let KeyState::Node(_key, offset) = self.index.search(&child_addr) {
+                    self.remove_child(offset);
+                }
+                self.index.clear(&child_addr);
+                continue;
+            }
+
+            match &mut self.content {
+                Content::Bits(vec) => {
+                    let mask = Self::word_mask(min_bit, first, last);
+                    Self::clear_bits_range(&mut self.index, vec, &child_addr, mask);
+                },
+                Content::Nodes(vec) => {
+                    Self::clear_nodes_range(&mut self.index, vec, &child_addr, last);
+                }
             }
         }
+        self.recount();
     }
 
-    // Return the state of the bit for this address
+    // Return the state of the bit for this address. Panics if
+    // the index points past our content.
     pub fn get(&self, addr: &Addr) -> bool {
+        self.try_get(addr).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Return the state of the bit for this address, or an error
+    // if the index points past our content
+    pub fn try_get(&self, addr: &Addr) -> Result<bool, BitFooError> {
+        let level = self.level();
+        let missing = || BitFooError::Invalid {
+            level,
+            bitno: addr.bitno() & !Addr::cardinality(level + 1),
+            reason: "index points past content",
+        };
         match self.index.search(addr) {
-            KeyState::Run(_key) => true,
+            KeyState::Run(_key) => Ok(true),
             KeyState::Node(_key, offset) => {
                 match &self.content {
                     Content::Bits(vec) => {
-                        vec[offset] & 0x1 << addr.key(0) > 0
+                        let bits = vec.get(offset).ok_or_else(missing)?;
+                        Ok(bits & 0x1 << addr.key(0) > 0)
                     },
                     Content::Nodes(vec) => {
-                        vec[offset].get(addr)
+                        vec.get(offset).ok_or_else(missing)?.try_get(addr)
                     }
                 }
             },
             KeyState::Missing(_key, _offset) => {
-                false
+                Ok(false)
             }
         }
     }
 
-    // Add the given node as a child
+    // Add the given node as a child. Panics if we are a
+    // level 1 node or the child can't be added.
     pub fn add_node(&mut self, node: Node) {
+        self.try_add_node(node).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Add the given node as our first child, returning an error
+    // if we are a level 1 node or the child doesn't fit. An empty
+    // or all-runs child is an error, as it would break the tree
+    // invariants (set a run in the index instead).
+    pub fn try_add_node(&mut self, node: Node) -> Result<(), BitFooError> {
+        let level = self.level();
+        let invalid = |reason| Err(BitFooError::Invalid { level, bitno: 0, reason });
+
         match &mut self.content {
             Content::Bits(_vec) => {
                 // Someone is mis-using interface
-                panic!("Cannot call add_node on level 1 node");
+                invalid("cannot add a child to a level 1 node")
+            },
+            Content::Nodes(_vec) if node.level() + 1 != level => {
+                invalid("child node at wrong level")
+            },
+            Content::Nodes(_vec) if !self.index.is_nodes_empty() || !self.index.is_runs_empty() => {
+                invalid("add_node needs an empty parent")
+            },
+            Content::Nodes(_vec) if node.is_empty() || node.index.is_all_runs() => {
+                invalid("child node is empty or full")
             },
             Content::Nodes(vec) => {
                 // NOTE: Should only be used to append
-                // the first node (called by BitFooVec)
+                // the first node (called by BitFooVec). A full
+                // level 8 child holds 2^64 bits, which is too
+                // many for our content count.
+                if node.count() > u64::MAX as u128 {
+                    return invalid("child node holds 2^64 bits");
+                }
+                self.content_count = node.count() as u64;
                 vec.push(node);
                 self.index.set_node_bit(0);
+                Ok(())
+            }
+        }
+    }
+
+    // Return our first child node. Used by BitFooVec to drop
+    // a root level, so is the reverse of add_node.
+    pub (crate) fn into_child(self) -> Option<Node> {
+        match self.content {
+            Content::Nodes(mut vec) if !vec.is_empty() => Some(vec.swap_remove(0)),
+            _ => None,
+        }
+    }
+
+    // Release unused capacity in this node and its children
+    pub fn shrink_to_fit(&mut self) {
+        match &mut self.content {
+            Content::Bits(vec) => vec.shrink_to_fit(),
+            Content::Nodes(vec) => {
+                vec.shrink_to_fit();
+                for node in vec.iter_mut() {
+                    node.shrink_to_fit();
+                }
+            },
+        }
+    }
+
+    // Iterator for bit numbers is given a starting Addr. Keys 
+    // above our level give our position, and the rest give
+    // the bit to seek to.
+    pub fn iter(&self, addr: Addr) -> NodeIterator<'_> {
+        NodeIterator::new(self, addr, u64::MAX)
+    }
+
+    // Iterator for bit numbers from the starting Addr up to
+    // the end bit number (inclusive)
+    pub fn iter_range(&self, addr: Addr, end: u64) -> NodeIterator<'_> {
+        NodeIterator::new(self, addr, end)
+    }
+
+    // Return the number of bits set in this node. This is a
+    // u128 as a full level 8 node holds 2^64 bits.
+    pub fn count(&self) -> u128 {
+        // Each run is a fully populated child, and the
+        // count for our content is kept as it changes
+        let run_bits = Addr::cardinality(self.level()) as u128 + 1;
+        let run_count = self.index.runs.count_ones() as u128 * run_bits;
+        run_count + self.content_count as u128
+    }
+
+    // Return the number of bits set from the starting Addr up
+    // to the end bit number (inclusive). Only the children at the
+    // edges of the range are partially counted.
+    pub fn count_range(&self, addr: &Addr, end: u64) -> u128 {
+        let start = addr.bitno();
+        let (start_key, end_key) = self.key_range(addr, end);
+        let mut count = 0;
+
+        for key_state in self.index.iter_range(start_key, end_key) {
+            match key_state {
+                KeyState::Run(key) => {
+                    let (min_bit, max_bit) = self.child_span(addr, key);
+                    let first = start.max(min_bit);
+                    let last = end.min(max_bit);
+                    count += (last - first) as u128 + 1;
+                },
+                KeyState::Node(key, offset) => {
+                    let (min_bit, max_bit) = self.child_span(addr, key);
+                    count += match &self.content {
+                        Content::Bits(vec) => {
+                            let mask = Self::word_mask(min_bit, start, end);
+                            (vec[offset] & mask).count_ones() as u128
+                        },
+                        Content::Nodes(vec) if start <= min_bit && max_bit <= end => {
+                            // Whole child is in range
+                            vec[offset].count()
+                        },
+                        Content::Nodes(vec) => {
+                            let child_addr = Addr::new(start.max(min_bit));
+                            vec[offset].count_range(&child_addr, end)
+                        },
+                    };
+                },
+                KeyState::Missing(_key, _offset) => (),
+            }
+        }
+        count
+    }
+
+    // Return the number of bits set below this address
+    pub fn rank(&self, addr: &Addr) -> u128 {
+        // Runs and child nodes before our key are counted whole
+        let key = self.index.key(addr);
+        let run_bits = Addr::cardinality(self.level()) as u128 + 1;
+        let runs_before = match self.index.runs.offset(key) {
+            Ok(offset) => offset,
+            Err(offset) => offset,
+        };
+        let offset = self.index.offset(addr);
+        let mut rank = runs_before as u128 * run_bits;
+
+        rank += match &self.content {
+            Content::Bits(vec) => {
+                vec[..offset].iter()
+                    .map(|bits| bits.count_ones() as u128).sum::<u128>()
+            },
+            Content::Nodes(vec) => {
+                vec[..offset].iter().map(|node| node.count()).sum::<u128>()
+            },
+        };
+
+        // Then descend into the child holding our key
+        rank + match self.index.search(addr) {
+            KeyState::Run(key) => {
+                let (min_bit, _max_bit) = self.child_span(addr, key);
+                (addr.bitno() - min_bit) as u128
+            },
+            KeyState::Node(_key, offset) => {
+                match &self.content {
+                    Content::Bits(vec) => {
+                        let mask = (1_u64 << addr.key(0)) - 1;
+                        (vec[offset] & mask).count_ones() as u128
+                    },
+                    Content::Nodes(vec) => vec[offset].rank(addr),
+                }
+            },
+            KeyState::Missing(_key, _offset) => 0,
+        }
+    }
+
+    // Return the position of the k'th set bit (counting from 0)
+    // given the Addr of this node, or None if fewer bits are set
+    pub fn select(&self, addr: &Addr, k: u128) -> Option<u64> {
+        let run_bits = Addr::cardinality(self.level()) as u128 + 1;
+        let mut k = k;
+
+        // Skip whole children until we find the one holding k
+        for key_state in self.index.iter() {
+            match key_state {
+                KeyState::Run(key) => {
+                    if k < run_bits {
+                        let (min_bit, _max_bit) = self.child_span(addr, key);
+                        return Some(min_bit + k as u64);
+                    }
+                    k -= run_bits;
+                },
+                KeyState::Node(key, offset) => {
+                    let (min_bit, _max_bit) = self.child_span(addr, key);
+                    match &self.content {
+                        Content::Bits(vec) => {
+                            let count = vec[offset].count_ones() as u128;
+                            if k < count {
+                                let bitno = select_word(vec[offset], k as u32);
+                                return Some(min_bit + bitno as u64);
+                            }
+                            k -= count;
+                        },
+                        Content::Nodes(vec) => {
+                            let count = vec[offset].count();
+                            if k < count {
+                                let child_addr = Addr::new(min_bit);
+                                return vec[offset].select(&child_addr, k);
+                            }
+                            k -= count;
+                        },
+                    }
+                },
+                KeyState::Missing(_key, _offset) => (),
             }
         }
+        None
+    }
+
+    // Iterator over the intervals of set bits, given the
+    // Addr of this node (keys above our level)
+    pub fn ranges(&self, addr: Addr) -> NodeRangeIterator<'_> {
+        NodeRangeIterator::new(self, addr)
+    }
+}
+
+// Helpers shared with the rest of the node module
+impl Node {
+    // Return the first and last bitno of the child at this key,
+    // given an Addr within this node
+    pub (super) fn child_span(&self, addr: &Addr, key: u8) -> (u64, u64) {
+        let level = self.level();
+        let mut addr = addr.clone();
+        addr.set(level, key);
+        (addr.min_bitno(level), addr.max_bitno(level))
+    }
+
+    // Return the highest valid key at our level. Level 8 only
+    // needs keys 0-3 to hold all u64's.
+    pub (super) fn max_key(&self) -> u8 {
+        Addr::new(u64::MAX).key(self.level())
+    }
+
+    // Return the first and last keys in this node overlapping
+    // the range from addr to end. If the range is empty the 
+    // first key is greater than the last.
+    pub (super) fn key_range(&self, addr: &Addr, end: u64) -> (u8, u8) {
+        let level = self.level();
+        let start = addr.bitno();
+
+        // NOTE: Shift of 64+ means the node holds all u64's
+        let shift = Addr::offset(level + 1) as u32;
+
+        if start > end {
+            (1, 0)
+        }
+        else if start.checked_shr(shift) == end.checked_shr(shift) {
+            // End is within this node
+            (addr.key(level), Addr::new(end).key(level))
+        }
+        else {
+            (addr.key(level), u8::MAX)
+        }
     }
 
-    // Iterator for bit numbers is given a starting Addr
-    pub fn iter(&self, addr: Addr) -> NodeIterator {
-        println!("*** Node.iter level={} addr={:?}", self.level(), addr);
-        NodeIterator::new(self, addr)
+    // Return a mask for the bits of the leaf word starting at 
+    // start_bit that fall within first_bit..=last_bit
+    pub (super) fn word_mask(start_bit: u64, first_bit: u64, last_bit: u64) 
+        -> u64 
+    {
+        let lo = first_bit.max(start_bit) - start_bit;
+        let hi = last_bit.min(start_bit + 63) - start_bit;
+        (u64::MAX << lo) & (u64::MAX >> (63 - hi))
     }
 }
 
@@ -111,48 +507,67 @@ impl Node {
 // a second mutable borrow on &self. Instead we are passing in the
 // structure elements as mustable references
 impl Node {
-    // Set a bit for a 'Bits' type content
-    fn set_bits(index: &mut KeyIndex, vec: &mut Vec<u64>, addr: &Addr) {
+    // Set bits in a leaf word for a 'Bits' type content. The
+    // content count is kept in step with the word.
+    fn set_bits(index: &mut KeyIndex, vec: &mut Vec<u64>, count: &mut u64,
+        addr: &Addr, bits: u64) 
+    {
         match index.search(addr) {
             KeyState::Run(_key) => (),  
             KeyState::Node(_key, offset) => {
                 // Update existing bitmask
-                let newbits = vec[offset] | 0x1 << addr.key(0);
+                let oldbits = vec[offset];
+                let newbits = oldbits | bits;
                 if newbits == u64::MAX {
                     // Run detected - remove node and update index
                     vec.remove(offset);
                     index.run(addr);
+                    *count -= oldbits.count_ones() as u64;
                 }
                 else {
                     // Just save the bits
                     vec[offset] = newbits;
+                    *count += (newbits ^ oldbits).count_ones() as u64;
                 }
             },
             KeyState::Missing(_key, offset) => {
-                // Just set - no run possible
-                vec.insert(offset, 0x1 << addr.key(0));
-                index.set(addr);
+                // Only a full word can be a run
+                if bits == u64::MAX {
+                    index.run(addr);
+                }
+                else {
+                    vec.insert(offset, bits);
+                    index.set(addr);
+                    *count += bits.count_ones() as u64;
+                }
             },
         }
     }
 
-    // Set a bit for a 'Nodes' type content
-    fn set_nodes(index: &mut KeyIndex, vec: &mut Vec<Node>, addr: &Addr) {
+    // Set bits in a leaf word for a 'Nodes' type content
+    fn set_nodes(index: &mut KeyIndex, vec: &mut Vec<Node>, count: &mut u64,
+        addr: &Addr, bits: u64) 
+    {
         match index.search(addr) {
             KeyState::Run(_key) => (),    // No-op to set on a run
             KeyState::Node(_key, offset) => {
-                // Tell child node to set bit
-                vec[offset].set(addr);
+                // Tell child node to set bits
+                *count -= vec[offset].count() as u64;
+                vec[offset].set_word(addr, bits);
                 if vec[offset].index.is_all_runs() {
                     // Run detected - remove node and update index
                     vec.remove(offset);
                     index.run(addr);
                 }
+                else {
+                    *count += vec[offset].count() as u64;
+                }
             },
             KeyState::Missing(_key, offset) => {
                 // Create the new child node
                 let mut node = Node::new(index.level - 1);
-                node.set(addr);
+                node.set_word(addr, bits);
+                *count += node.count() as u64;
                 vec.insert(offset, node);
                 index.set(addr);
             },
@@ -160,20 +575,23 @@ impl Node {
     }
     
     // Clear a bit for a 'Bits' type content
-    fn clear_bits(index: &mut KeyIndex, vec: &mut Vec<u64>, addr: &Addr) {
+    fn clear_bits(index: &mut KeyIndex, vec: &mut Vec<u64>, count: &mut u64,
+        addr: &Addr) 
+    {
         match index.search(addr) {
             KeyState::Run(_key) => {
                 // It's not longer a run, so need to add a u64 to our 
                 // content vector with all bits set but the cleared bit.
-                // This will be the only element in the vector (offset=0)
                 let bitmask = !(0x1 << addr.key(0));  
-                vec.push(bitmask);
+                vec.insert(index.offset(addr), bitmask);
                 index.set(addr); 
+                *count += 63;
             },
             KeyState::Node(_key, offset) => {
                 // Update existing bitmask
                 let bitmask = !(0x1 << addr.key(0));  
                 let newbits = vec[offset] & bitmask;
+                *count -= (vec[offset] ^ newbits).count_ones() as u64;
 
                 if newbits == 0 {
                     // Node is all 0's, so remove
@@ -190,7 +608,9 @@ impl Node {
     }
     
     // Clear a bit for a 'Nodes' type content
-    fn clear_nodes(index: &mut KeyIndex, vec: &mut Vec<Node>, addr: &Addr) {
+    fn clear_nodes(index: &mut KeyIndex, vec: &mut Vec<Node>, count: &mut u64,
+        addr: &Addr) 
+    {
         match index.search(addr) {
             KeyState::Run(_key) => {
                 // Insert a node with 'all runs' index, then
@@ -198,20 +618,168 @@ impl Node {
                 let mut node = Node::new(index.level - 1);
                 node.index.set_all_runs();
                 node.clear(addr);
+                *count += node.count() as u64;
 
-                // Add to our vector and set the index (offset 0)
-                vec.push(node); 
+                // Add to our vector and set the index
+                vec.insert(index.offset(addr), node); 
                 index.set(addr);  
             },
             KeyState::Node(_key, offset) => {
+                // Only remove the child once it is empty
+                *count -= vec[offset].count() as u64;
                 vec[offset].clear(addr);
-                index.clear(addr);
+                if vec[offset].is_empty() {
+                    vec.remove(offset);
+                    index.clear(addr);
+                }
+                else {
+                    *count += vec[offset].count() as u64;
+                }
             },
             KeyState::Missing(_key, _offset) => (), // No-op if all 0's
         }
     }
 }
 
+// Private helpers for range updates. As above, the index and
+// content vector are passed in separately.
+impl Node {
+    // Update the content count after a change
+    pub (super) fn recount(&mut self) {
+        self.content_count = self.content_bits();
+    }
+
+    // Return the bits set in our content. Child nodes keep
+    // their own counts, so only our content is visited.
+    pub (super) fn content_bits(&self) -> u64 {
+        match &self.content {
+            Content::Bits(vec) => {
+                vec.iter().map(|bits| bits.count_ones() as u64).sum()
+            },
+            Content::Nodes(vec) => {
+                // A level 7 child holds up to 2^62 bits, and a full
+                // child is kept as a run rather than content, so even
+                // a level 8 node's content stays below 2^64 bits.
+                // Level 9 only takes one child through add_node.
+                vec.iter().map(|node| node.count() as u64).sum()
+            },
+        }
+    }
+
+    // Remove the child at this content offset
+    fn remove_child(&mut self, offset: usize) {
+        match &mut self.content {
+            Content::Bits(vec) => { vec.remove(offset); },
+            Content::Nodes(vec) => { vec.remove(offset); },
+        }
+    }
+
+    // Set the masked bits of a word for a 'Bits' type content
+    fn set_bits_range(index: &mut KeyIndex, vec: &mut Vec<u64>, 
+                      addr: &Addr, mask: u64) {
+        match index.search(addr) {
+            KeyState::Run(_key) => (),
+            KeyState::Node(_key, offset) => {
+                let newbits = vec[offset] | mask;
+                if newbits == u64::MAX {
+                    // Run detected - remove node and update index
+                    vec.remove(offset);
+                    index.run(addr);
+                }
+                else {
+                    vec[offset] = newbits;
+                }
+            },
+            KeyState::Missing(_key, offset) => {
+                // Mask is never full here, so no run possible
+                vec.insert(offset, mask);
+                index.set(addr);
+            },
+        }
+    }
+
+    // Set a range within one child for a 'Nodes' type content
+    fn set_nodes_range(index: &mut KeyIndex, vec: &mut Vec<Node>, 
+                       addr: &Addr, end: u64) {
+        match index.search(addr) {
+            KeyState::Run(_key) => (),
+            KeyState::Node(_key, offset) => {
+                vec[offset].set_range(addr, end);
+                if vec[offset].index.is_all_runs() {
+                    // Run detected - remove node and update index
+                    vec.remove(offset);
+                    index.run(addr);
+                }
+            },
+            KeyState::Missing(_key, offset) => {
+                // Range doesn't cover the child, so no run possible
+                let mut node = Node::new(index.level - 1);
+                node.set_range(addr, end);
+                vec.insert(offset, node);
+                index.set(addr);
+            },
+        }
+    }
+
+    // Clear the masked bits of a word for a 'Bits' type content
+    fn clear_bits_range(index: &mut KeyIndex, vec: &mut Vec<u64>, 
+                        addr: &Addr, mask: u64) {
+        match index.search(addr) {
+            KeyState::Run(_key) => {
+                // Split the run into a word with the mask cleared
+                vec.insert(index.offset(addr), !mask);
+                index.set(addr);
+            },
+            KeyState::Node(_key, offset) => {
+                let newbits = vec[offset] & !mask;
+                if newbits == 0 {
+                    // Node is all 0's, so remove
+                    vec.remove(offset);
+                    index.clear(addr);
+                }
+                else {
+                    vec[offset] = newbits;
+                }
+            },
+            KeyState::Missing(_key, _offset) => (),
+        }
+    }
+
+    // Clear a range within one child for a 'Nodes' type content
+    fn clear_nodes_range(index: &mut KeyIndex, vec: &mut Vec<Node>, 
+                         addr: &Addr, end: u64) {
+        match index.search(addr) {
+            KeyState::Run(_key) => {
+                // Split the run into an 'all runs' node, then 
+                // clear the range from it
+                let mut node = Node::new(index.level - 1);
+                node.index.set_all_runs();
+                node.clear_range(addr, end);
+                vec.insert(index.offset(addr), node);
+                index.set(addr);
+            },
+            KeyState::Node(_key, offset) => {
+                vec[offset].clear_range(addr, end);
+                if vec[offset].is_empty() {
+                    vec.remove(offset);
+                    index.clear(addr);
+                }
+            },
+            KeyState::Missing(_key, _offset) => (),
+        }
+    }
+}
+
+// Return the position of the k'th set bit in the word
+fn select_word(bits: u64, k: u32) -> u32 {
+    // Clear the lowest k set bits
+    let mut bits = bits;
+    for _ in 0..k {
+        bits &= bits - 1;
+    }
+    bits.trailing_zeros()
+}
+
 // Clone interface
 impl Clone for Node {
     fn clone(&self) -> Node {
@@ -222,7 +790,43 @@ impl Clone for Node {
 
         Node { 
             index: self.index.clone(),
-            content: content
+            content,
+            content_count: self.content_count,
+        }
+    }
+}
+
+// Comparison interface. Nodes are compared as the sets of bits
+// they hold, so the same bits compare equal however they are
+// stored, and are ordered by their sorted bit sequence.
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.ranges(Addr::new(0)).eq(other.ranges(Addr::new(0)))
+    }
+}
+
+impl Eq for Node {}
+
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut count: usize = 0;
+        for range in self.ranges(Addr::new(0)) {
+            range.start().hash(state);
+            range.end().hash(state);
+            count += 1;
         }
+        count.hash(state);
     }
-}
\ No newline at end of file
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_ranges(self.ranges(Addr::new(0)), other.ranges(Addr::new(0)))
+    }
+}
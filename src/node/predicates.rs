@@ -0,0 +1,155 @@
+use crate::Addr;
+use super::Node;
+use super::algebra::Child;
+
+// Set predicates, which walk both trees together. The KeyIndex
+// bitmaps are compared a level at a time so most keys are settled
+// without visiting their children, and no result tree is built.
+// The two nodes are both taken to start at bit 0, and may be at
+// different levels.
+impl Node {
+    // Return true if every bit we hold is also set in other
+    pub fn is_subset(&self, other: &Node) -> bool {
+        if self.level() > other.level() && self.has_bits_above(other.level()) {
+            return false;
+        }
+        let level = self.level().min(other.level());
+        is_subset(self.lower(level), other.lower(level))
+    }
+
+    // Return true if every bit in other is also set here
+    pub fn is_superset(&self, other: &Node) -> bool {
+        other.is_subset(self)
+    }
+
+    // Return true if we share any bit with other
+    pub fn intersects(&self, other: &Node) -> bool {
+        let level = self.level().min(other.level());
+        intersects(self.lower(level), other.lower(level))
+    }
+
+    // Return true if we share no bits with other
+    pub fn is_disjoint(&self, other: &Node) -> bool {
+        !self.intersects(other)
+    }
+
+    // Return the number of bits set in both
+    pub fn intersection_count(&self, other: &Node) -> u128 {
+        let level = self.level().min(other.level());
+        let span = Addr::cardinality(level + 1) as u128 + 1;
+        intersection_count(self.lower(level), other.lower(level), span)
+    }
+}
+
+// Private helpers
+impl Node {
+    // Return the part of our tree covering the span of a node at 
+    // the given level, following key 0 down from our level
+    fn lower(&self, level: u8) -> Child<'_> {
+        let mut node = self;
+        while node.level() > level {
+            match node.child_at(0) {
+                Child::Node(child) => node = child,
+                child => return child,
+            }
+        }
+
+        match node.is_empty() {
+            true => Child::Missing,
+            false => Child::Node(node),
+        }
+    }
+
+    // Return true if we have bits past the span of a node at the
+    // given level (i.e. under any key but 0 on the way down)
+    fn has_bits_above(&self, level: u8) -> bool {
+        let mut node = self;
+        while node.level() > level {
+            let keys = &node.index.nodes | &node.index.runs;
+            if keys.next_set(1).is_some() {
+                return true;
+            }
+            match node.child_at(0) {
+                Child::Node(child) => node = child,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    fn node_is_subset(&self, other: &Node) -> bool {
+        // Every key we hold must be held by other, and our runs
+        // must be runs there too
+        let keys = &self.index.nodes | &self.index.runs;
+        let other_keys = &other.index.nodes | &other.index.runs;
+        if &keys | &other_keys != other_keys || 
+            &self.index.runs | &other.index.runs != other.index.runs 
+        {
+            return false;
+        }
+
+        // Only nodes on both sides are left to check
+        let both = &self.index.nodes & &other.index.nodes;
+        both.iter().all(|key| is_subset(self.child_at(key), other.child_at(key)))
+    }
+
+    fn node_intersects(&self, other: &Node) -> bool {
+        // Children are never empty, so a run against anything 
+        // intersects
+        let keys = &self.index.nodes | &self.index.runs;
+        let other_keys = &other.index.nodes | &other.index.runs;
+        let shared = &keys & &other_keys;
+        if !(&shared & &self.index.runs).is_empty() || 
+            !(&shared & &other.index.runs).is_empty() 
+        {
+            return true;
+        }
+
+        shared.iter().any(|key| intersects(self.child_at(key), other.child_at(key)))
+    }
+
+    fn node_intersection_count(&self, other: &Node) -> u128 {
+        let keys = &self.index.nodes | &self.index.runs;
+        let other_keys = &other.index.nodes | &other.index.runs;
+        let span = Addr::cardinality(self.level()) as u128 + 1;
+
+        (&keys & &other_keys).iter()
+            .map(|key| intersection_count(self.child_at(key), other.child_at(key), span))
+            .sum()
+    }
+}
+
+fn is_subset(a: Child, b: Child) -> bool {
+    match (a, b) {
+        (Child::Missing, _) | (_, Child::Run) => true,
+        (_, Child::Missing) | (Child::Run, _) => false,
+        (Child::Bits(x), Child::Bits(y)) => x & !y == 0,
+        (Child::Node(x), Child::Node(y)) => x.node_is_subset(y),
+        _ => false,  // Not possible at the same level
+    }
+}
+
+fn intersects(a: Child, b: Child) -> bool {
+    match (a, b) {
+        (Child::Missing, _) | (_, Child::Missing) => false,
+        (Child::Run, _) | (_, Child::Run) => true,
+        (Child::Bits(x), Child::Bits(y)) => x & y != 0,
+        (Child::Node(x), Child::Node(y)) => x.node_intersects(y),
+        _ => false,  // Not possible at the same level
+    }
+}
+
+// Bits set in both children, each covering span bits
+fn intersection_count(a: Child, b: Child, span: u128) -> u128 {
+    match (a, b) {
+        (Child::Missing, _) | (_, Child::Missing) => 0,
+        (Child::Run, Child::Run) => span,
+        (Child::Run, Child::Bits(x)) | (Child::Bits(x), Child::Run) => {
+            x.count_ones() as u128
+        },
+        (Child::Run, Child::Node(x)) | (Child::Node(x), Child::Run) => x.count(),
+        (Child::Bits(x), Child::Bits(y)) => (x & y).count_ones() as u128,
+        (Child::Node(x), Child::Node(y)) => x.node_intersection_count(y),
+        _ => 0,  // Not possible at the same level
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{Addr,KeyState,KeyIndexIterator};
+use std::cmp::Ordering;
+use std::iter::Iterator;
+use std::ops::RangeInclusive;
+use super::Node;
+use super::Content;
+
+// Iterators for the intervals in each type of child
+// content. A run is always a single interval.
+enum ChildRanges<'a> {
+    End,
+    Run(Option<RangeInclusive<u64>>),
+    Bits(WordRanges),
+    Node(Box<NodeRangeIterator<'a>>),
+}
+
+impl<'a> ChildRanges<'a> {
+    fn next(&mut self) -> Option<RangeInclusive<u64>> {
+        match self {
+            ChildRanges::Run(range) => range.take(),
+            ChildRanges::Bits(iter) => iter.next(),
+            ChildRanges::Node(iter) => iter.next(),
+            ChildRanges::End => None,
+        }
+    }
+}
+
+// Iterator for the runs of 1's within a leaf u64
+struct WordRanges {
+    start_bit: u64,
+    bits: u64,      // Remaining bits - cleared as they are returned
+}
+
+impl Iterator for WordRanges {
+    type Item = RangeInclusive<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bits == 0 {
+            None
+        }
+        else {
+            // Find the lowest set bit and the 1's following it
+            let lo = self.bits.trailing_zeros();
+            let len = (self.bits >> lo).trailing_ones();
+            let hi = lo + len - 1;
+
+            // Clear the bits we're returning
+            self.bits = match hi {
+                63 => 0,
+                _ => self.bits & (u64::MAX << (hi + 1)),
+            };
+            Some(self.start_bit + lo as u64..=self.start_bit + hi as u64)
+        }
+    }
+}
+
+// Iterator over the maximal intervals of set bits in a node.
+// Intervals from adjacent children are merged, so the number
+// of steps scales with the number of intervals, not bits.
+pub struct NodeRangeIterator<'a> {
+    addr: Addr,     // Our position (keys above our level)
+    node: &'a Node,
+    index_iter: KeyIndexIterator<'a>,
+    child_iter: ChildRanges<'a>,
+    pending: Option<RangeInclusive<u64>>, // Interval being merged
+}
+
+impl<'a> NodeRangeIterator<'a> {
+    pub fn new(node: &'a Node, addr: Addr) -> NodeRangeIterator<'a> {
+        NodeRangeIterator {
+            addr,
+            node,
+            index_iter: node.index.iter(),
+            child_iter: ChildRanges::End,
+            pending: None,
+        }
+    }
+
+    // Return the child interval iterator for the given key state
+    fn child_ranges(&self, key_state: KeyState) -> ChildRanges<'a> {
+        match key_state {
+            KeyState::Node(key, offset) => {
+                let (min_bit, _max_bit) = self.node.child_span(&self.addr, key);
+                match &self.node.content {
+                    Content::Bits(vec) => {
+                        ChildRanges::Bits(WordRanges {
+                            start_bit: min_bit,
+                            bits: vec[offset],
+                        })
+                    },
+                    Content::Nodes(vec) => {
+                        ChildRanges::Node(
+                            Box::new(vec[offset].ranges(Addr::new(min_bit)))
+                        )
+                    },
+                }
+            },
+            KeyState::Run(key) => {
+                let (min_bit, max_bit) = self.node.child_span(&self.addr, key);
+                ChildRanges::Run(Some(min_bit..=max_bit))
+            },
+            KeyState::Missing(_key, _offset) => ChildRanges::End,
+        }
+    }
+
+    // Return the next (unmerged) interval from our children
+    fn next_from_children(&mut self) -> Option<RangeInclusive<u64>> {
+        loop {
+            if let Some(range) = self.child_iter.next() {
+                return Some(range);
+            }
+
+            let key_state = self.index_iter.next()?;
+            self.child_iter = self.child_ranges(key_state);
+        }
+    }
+}
+
+// Compare two sets of maximal intervals as their sorted bit
+// sequences, lexicographically. This steps through intervals
+// rather than bits.
+pub (crate) fn cmp_ranges<A, B>(mut a: A, mut b: B) -> Ordering
+    where A: Iterator<Item = RangeInclusive<u64>>,
+          B: Iterator<Item = RangeInclusive<u64>>
+{
+    loop {
+        let (ra, rb) = match (a.next(), b.next()) {
+            (Some(ra), Some(rb)) => (ra, rb),
+            (ra, rb) => return ra.is_some().cmp(&rb.is_some()),
+        };
+
+        // First bits differ
+        if ra.start() != rb.start() {
+            return ra.start().cmp(rb.start());
+        }
+
+        // Where one interval ends first its next bit is past a
+        // gap, so it's greater unless there are no more bits
+        match ra.end().cmp(rb.end()) {
+            Ordering::Equal => (),
+            Ordering::Less => {
+                return match a.next() {
+                    Some(_range) => Ordering::Greater,
+                    None => Ordering::Less,
+                };
+            },
+            Ordering::Greater => {
+                return match b.next() {
+                    Some(_range) => Ordering::Less,
+                    None => Ordering::Greater,
+                };
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for NodeRangeIterator<'a> {
+    type Item = RangeInclusive<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(range) = self.next_from_children() {
+            match self.pending.take() {
+                Some(pending) if *pending.end() + 1 == *range.start() => {
+                    // Adjacent - extend the pending interval
+                    self.pending = Some(*pending.start()..=*range.end());
+                },
+                Some(pending) => {
+                    // Gap found, so the pending interval is complete
+                    self.pending = Some(range);
+                    return Some(pending);
+                },
+                None => {
+                    self.pending = Some(range);
+                },
+            }
+        }
+
+        // No more children - return whatever is left
+        self.pending.take()
+    }
+}
@@ -0,0 +1,41 @@
+use crate::BitFooStats;
+use std::mem::size_of;
+use super::Node;
+use super::Content;
+
+// Memory use and shape of the tree under a node
+impl Node {
+    // Return the bytes allocated on the heap by this node and its
+    // children. Doesn't include the node itself.
+    pub fn heap_size(&self) -> usize {
+        match &self.content {
+            Content::Bits(vec) => vec.capacity() * size_of::<u64>(),
+            Content::Nodes(vec) => {
+                vec.capacity() * size_of::<Node>() + 
+                    vec.iter().map(|node| node.heap_size()).sum::<usize>()
+            },
+        }
+    }
+
+    // Add this node and its children to the stats
+    pub (crate) fn add_stats(&self, stats: &mut BitFooStats) {
+        let level = self.level() as usize;
+        stats.nodes[level] += 1;
+        stats.runs[level] += self.index.runs.count_ones() as u64;
+
+        match &self.content {
+            Content::Bits(vec) => {
+                stats.leaf_words += vec.len() as u64;
+                stats.heap_size += vec.capacity() * size_of::<u64>();
+                stats.wasted_capacity += (vec.capacity() - vec.len()) * size_of::<u64>();
+            },
+            Content::Nodes(vec) => {
+                stats.heap_size += vec.capacity() * size_of::<Node>();
+                stats.wasted_capacity += (vec.capacity() - vec.len()) * size_of::<Node>();
+                for node in vec {
+                    node.add_stats(stats);
+                }
+            },
+        }
+    }
+}
@@ -59,6 +59,64 @@ fn node_clear_all_l1() {
     assert_eq!(node.index.is_runs_full(), false);
 }
 
+#[test]
+fn clear_splits_run_in_place() {
+    // Partial words either side of a run, then clear a bit
+    // inside the run. The split word must go between them.
+    let mut node = Node::new(1);
+    let mut bits: Vec<u64> = (128..192).collect();
+    bits.push(64);
+    bits.push(200);
+    for b in &bits {
+        node.set(&Addr::new(*b));
+    }
+    node.clear(&Addr::new(130));
+
+    for b in 0..256 {
+        let expected = b != 130 && bits.contains(&b);
+        assert_eq!(node.get(&Addr::new(b)), expected, "bit {}", b);
+    }
+
+    // Same again for a run of a whole level 1 child
+    let mut node = Node::new(2);
+    for b in (1 << 14)..(2 << 14) {
+        node.set(&Addr::new(b));
+    }
+    node.set(&Addr::new(5));
+    node.set(&Addr::new((2 << 14) + 7));
+    node.clear(&Addr::new((1 << 14) + 100));
+
+    for b in 0..(3 << 14) {
+        let expected = match b {
+            5 => true,
+            b if b == (1 << 14) + 100 => false,
+            b if ((1 << 14)..(2 << 14)).contains(&b) => true,
+            b => b == (2 << 14) + 7,
+        };
+        assert_eq!(node.get(&Addr::new(b)), expected, "bit {}", b);
+    }
+}
+
+#[test]
+fn clear_keeps_busy_child() {
+    // Clearing one bit of a child holding several must
+    // leave the child, and its other bits, in place
+    let mut node = Node::new(3);
+    let bits = [5_u64, 6, 1 << 20, (1 << 22) + 9];
+    for b in &bits {
+        node.set(&Addr::new(*b));
+    }
+    node.clear(&Addr::new(5));
+
+    assert!(!node.get(&Addr::new(5)));
+    for b in &bits[1..] {
+        assert!(node.get(&Addr::new(*b)), "bit {}", b);
+    }
+    node.clear(&Addr::new(6));
+    node.clear(&Addr::new(1 << 20));
+    assert!(node.get(&Addr::new((1 << 22) + 9)));
+}
+
 #[test]
 fn node_set_all_l1() {
     // Set all
@@ -182,3 +240,213 @@ fn iter_node() {
     println!("node is {:?}", node);
     assert_eq!(in_bits, out_bits);
 }
+
+#[test]
+fn iter_seek() {
+    // The starting Addr is a seek position within the node
+    let mut node = Node::new(8);
+    let in_bits = vec![1_u64, 0xff, 0xff_00_01, 0xff_ff_ff_ff, u64::MAX];
+    for b in &in_bits {
+        node.set(&Addr::new(*b));
+    }
+
+    let out_bits: Vec<u64> = node.iter(Addr::new(0x100)).collect();
+    assert_eq!(&in_bits[2..], &out_bits[..]);
+
+    let out_bits: Vec<u64> = node.iter_range(Addr::new(2), 0xff_00_01)
+        .collect();
+    assert_eq!(&in_bits[1..3], &out_bits[..]);
+}
+
+#[test]
+fn ranges_high_run() {
+    // A level 8 run covers 2^62 bits but is a single interval
+    let mut node = Node::new(8);
+    node.index.run(&Addr::new(1 << 62));
+    node.set(&Addr::new(5));
+    node.set(&Addr::new((2 << 62) + 1));
+
+    let out: Vec<std::ops::RangeInclusive<u64>> = node.ranges(Addr::new(0))
+        .collect();
+    assert_eq!(out, vec![5..=5, 1 << 62..=(2 << 62) - 1, 
+        (2 << 62) + 1..=(2 << 62) + 1]);
+}
+
+#[test]
+fn count_runs() {
+    // Runs are counted from the cardinality of the level
+    let mut node = Node::new(8);
+    node.index.run(&Addr::new(1 << 62));
+    node.set(&Addr::new(5));
+    assert_eq!(node.count(), (1 << 62) + 1);
+    assert_eq!(node.count_range(&Addr::new(6), (1 << 62) + 9), 10);
+
+    // A full level 8 node (keys 0-3) holds every u64
+    let mut node = Node::new(8);
+    for key in 0..4 {
+        node.index.run(&Addr::new(key << 62));
+    }
+    assert_eq!(node.count(), 1 << 64);
+}
+
+#[test]
+fn rank_select_runs() {
+    // A run child counts as a fully populated subtree
+    let mut node = Node::new(8);
+    node.set(&Addr::new(5));
+    node.index.run(&Addr::new(1 << 62));
+    node.set(&Addr::new(3 << 62));
+
+    assert_eq!(node.rank(&Addr::new(5)), 0);
+    assert_eq!(node.rank(&Addr::new((1 << 62) + 10)), 11);
+    assert_eq!(node.rank(&Addr::new(3 << 62)), (1 << 62) + 1);
+
+    let base = Addr::new(0);
+    assert_eq!(node.select(&base, 0), Some(5));
+    assert_eq!(node.select(&base, 1), Some(1 << 62));
+    assert_eq!(node.select(&base, 1 << 62), Some((2 << 62) - 1));
+    assert_eq!(node.select(&base, (1 << 62) + 1), Some(3 << 62));
+    assert_eq!(node.select(&base, (1 << 62) + 2), None);
+}
+
+#[test]
+fn node_eq() {
+    // Level doesn't matter, only the bits held
+    let mut a = Node::new(1);
+    let mut b = Node::new(3);
+    a.set(&Addr::new(100));
+    b.set(&Addr::new(100));
+    assert_eq!(a, b);
+
+    b.set(&Addr::new(1 << 20));
+    assert_ne!(a, b);
+    assert!(a < b);
+    b.clear(&Addr::new(1 << 20));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn validate() {
+    use crate::BitFooError;
+    use super::Content;
+
+    let reason = |node: &Node| match node.validate() {
+        Err(BitFooError::Invalid { reason, .. }) => reason,
+        other => panic!("expected invalid, got {:?}", other),
+    };
+
+    let mut node = Node::new(3);
+    node.set(&Addr::new(5));
+    node.set(&Addr::new((1 << 22) + 5));
+    node.set_range(&Addr::new(1 << 15), (1 << 16) - 1);
+    assert!(node.validate().is_ok());
+    assert!(Node::new(8).validate().is_ok());
+
+    // Index and content out of step
+    let mut bad = node.clone();
+    bad.index.runs.set(0);
+    assert_eq!(reason(&bad), "key is both a node and a run");
+    let mut bad = node.clone();
+    bad.index.nodes.set(7);
+    assert_eq!(reason(&bad), "child node count doesn't match index");
+
+    // Problems deeper down report the node they're in
+    let mut bad = node.clone();
+    if let Content::Nodes(vec) = &mut bad.content {
+        vec[1].index.nodes.set(3);
+    }
+    match bad.validate() {
+        Err(BitFooError::Invalid { level, bitno, reason }) => {
+            assert_eq!((level, bitno), (2, 1 << 22));
+            assert_eq!(reason, "child node count doesn't match index");
+        },
+        other => panic!("expected invalid, got {:?}", other),
+    }
+
+    // Empty children should have been removed
+    let mut bad = Node::new(2);
+    bad.index.nodes.set(0);
+    bad.content = Content::Nodes(vec![Node::new(1)]);
+    assert_eq!(reason(&bad), "child node is empty or full");
+}
+
+#[test]
+fn try_variants() {
+    use crate::BitFooError;
+    use super::Content;
+
+    assert!(matches!(Node::try_new(0), Err(BitFooError::BadLevel(0))));
+    assert!(matches!(Node::try_new(10), Err(BitFooError::BadLevel(10))));
+    assert_eq!(Node::try_new(9).unwrap().level(), 9);
+
+    // Children only go in an empty parent one level up
+    let mut leaf = Node::new(1);
+    assert!(leaf.try_add_node(Node::new(1)).is_err());
+    let mut parent = Node::new(3);
+    assert!(parent.try_add_node(Node::new(1)).is_err());
+    let mut child = Node::new(2);
+    child.set(&Addr::new(1 << 15));
+    assert!(parent.try_add_node(child.clone()).is_ok());
+    assert!(parent.try_add_node(child).is_err());
+    assert!(parent.try_get(&Addr::new(1 << 15)).unwrap());
+
+    // Empty and all-runs children would fail validate()
+    let reason = |result| match result {
+        Err(BitFooError::Invalid { reason, .. }) => reason,
+        other => panic!("{:?}", other),
+    };
+    let mut parent = Node::new(3);
+    assert_eq!(reason(parent.try_add_node(Node::new(2))), "child node is empty or full");
+    let mut child = Node::new(2);
+    child.index.set_all_runs();
+    assert_eq!(reason(parent.try_add_node(child)), "child node is empty or full");
+    assert!(parent.is_empty());
+
+    // A level 8 child with every bit set can't be counted
+    let mut child = Node::new(8);
+    child.set_range(&Addr::new(0), u64::MAX);
+    assert_eq!(child.count(), 1 << 64);
+    let mut parent = Node::new(9);
+    assert_eq!(reason(parent.try_add_node(child)), "child node holds 2^64 bits");
+
+    // An index pointing past the content is an error, not a panic
+    let mut bad = Node::new(2);
+    bad.set(&Addr::new(1 << 15));
+    bad.content = Content::Nodes(Vec::new());
+    assert!(matches!(bad.try_get(&Addr::new(1 << 15)),
+                     Err(BitFooError::Invalid { level: 2, bitno: 0, .. })));
+    assert!(!bad.try_get(&Addr::new(5)).unwrap());
+}
+
+#[test]
+fn rank_select_scale() {
+    use std::time::{Duration,Instant};
+
+    // Time queries on a tree of bits spread over the whole
+    // u64 range, so it is deep and has many nodes
+    let time_queries = |len: u64| -> Duration {
+        let mut bits: Vec<u64> = (1..=len)
+            .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+            .collect();
+        bits.sort_unstable();
+        let mut node = Node::new(8);
+        for b in &bits {
+            node.set(&Addr::new(*b));
+        }
+        assert_eq!(node.count(), len as u128);
+
+        let start = Instant::now();
+        for k in (0..bits.len()).step_by(bits.len() / 100) {
+            assert_eq!(node.rank(&Addr::new(bits[k])), k as u128);
+            assert_eq!(node.select(&Addr::new(0), k as u128), Some(bits[k]));
+        }
+        start.elapsed()
+    };
+
+    // Counts are kept per node, so queries only look at the
+    // siblings on their path down. Walking each subtree would
+    // make the 100x larger tree about 100x slower.
+    let small = time_queries(2000);
+    let large = time_queries(200000);
+    assert!(large < small * 20, "small {:?} large {:?}", small, large);
+}
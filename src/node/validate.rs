@@ -0,0 +1,64 @@
+use crate::{Addr,BitFooError};
+use super::Node;
+use super::Content;
+
+// Structural checks for a node tree. The rest of the code assumes
+// these hold, and gives wrong answers rather than failing if not.
+impl Node {
+    // Check the invariants for this node and everything under it,
+    // taking the node to start at bit 0
+    pub fn validate(&self) -> Result<(), BitFooError> {
+        self.validate_at(0)
+    }
+}
+
+// Private helpers
+impl Node {
+    // As above for a node starting at base
+    fn validate_at(&self, base: u64) -> Result<(), BitFooError> {
+        let level = self.level();
+        let invalid = |reason| Err(BitFooError::Invalid { level, bitno: base, reason });
+
+        if !(&self.index.nodes & &self.index.runs).is_empty() {
+            return invalid("key is both a node and a run");
+        }
+        let keys = &self.index.nodes | &self.index.runs;
+        let max_key = self.max_key();
+        if max_key < u8::MAX && keys.next_set(max_key + 1).is_some() {
+            return invalid("key out of range for level");
+        }
+
+        let count = self.index.nodes.count_ones() as usize;
+        let span = Addr::cardinality(level) + 1;
+        match (&self.content, level) {
+            (Content::Bits(vec), 1) => {
+                if vec.len() != count {
+                    return invalid("leaf word count doesn't match index");
+                }
+                if vec.iter().any(|bits| *bits == 0 || *bits == u64::MAX) {
+                    return invalid("leaf word is empty or full");
+                }
+            },
+            (Content::Nodes(vec), 2..=9) => {
+                if vec.len() != count {
+                    return invalid("child node count doesn't match index");
+                }
+                for (child, key) in vec.iter().zip(self.index.nodes.iter()) {
+                    if child.level() != level - 1 {
+                        return invalid("child node at wrong level");
+                    }
+                    if child.is_empty() || child.index.is_all_runs() {
+                        return invalid("child node is empty or full");
+                    }
+                    child.validate_at(base + key as u64 * span)?;
+                }
+            },
+            _ => return invalid("content type doesn't match level"),
+        }
+
+        if self.content_bits() != self.content_count {
+            return invalid("cached count doesn't match content");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+use crate::{BitFooVec,BitFooError};
+use std::io::Read;
+use std::ops::RangeInclusive;
+
+// Roaring portable serialization. Each 32-bit bitmap splits its
+// values by the high 16 bits into containers, which are either a
+// sorted array of u16s, a 2^16 bit bitmap or a list of runs:
+//
+//   cookie    u32       NO_RUN_COOKIE, followed by a u32 count
+//                       or RUN_COOKIE | (count - 1) << 16, followed
+//                       by a bitset of which containers are runs
+//   headers   count x   u16 high key, u16 cardinality - 1
+//   offsets   count x   u32 byte offset of each container. Left
+//                       out if there are runs and count < 4.
+//   containers          array: cardinality x u16
+//                       bitmap: 1024 x u64
+//                       run: u16 count, then u16 start, u16 length - 1
+//
+// The 64-bit treemap is a u64 count of 32-bit bitmaps, each
+// preceded by its u32 high key. All values are little endian.
+//
+// Runs map onto KeyIndex runs via set_range(), and arrays and
+// bitmaps are written a leaf word at a time.
+const NO_RUN_COOKIE: u32 = 12346;
+const RUN_COOKIE: u32 = 12347;
+const NO_OFFSET_THRESHOLD: usize = 4;
+const MAX_CONTAINERS: usize = 1 << 16;
+const MAX_TREEMAP_CONTAINERS: u64 = 1 << 24;
+const MAX_ARRAY: u32 = 4096;
+const BITMAP_WORDS: usize = 1024;
+
+// Public interface
+impl BitFooVec {
+    // Return the 32-bit Roaring serialization. Fails if any bit
+    // is 2^32 or above - use to_roaring_treemap_bytes() for those.
+    pub fn to_roaring_bytes(&self) -> Result<Vec<u8>, BitFooError> {
+        // Check before building, so there are at most 2^16 containers
+        if let Some(last) = self.prev_set(u64::MAX) {
+            if last > u32::MAX as u64 {
+                return Err(BitFooError::OutOfRange(last));
+            }
+        }
+
+        let containers = containers(self);
+        let mut out = Vec::new();
+        write_bitmap(&mut out, &containers);
+        Ok(out)
+    }
+
+    // Read a 32-bit Roaring serialization
+    pub fn from_roaring_bytes(bytes: &[u8]) -> Result<Self, BitFooError> {
+        let mut bv = BitFooVec::new();
+        let mut input = bytes;
+        read_bitmap(&mut input, 0, &mut bv)?;
+        finish(input, bv)
+    }
+
+    // Return the 64-bit Roaring treemap serialization. Each 2^16
+    // bits with any set needs a container, even if it is a run, so
+    // this fails if more than 2^24 containers would be written.
+    pub fn to_roaring_treemap_bytes(&self) -> Result<Vec<u8>, BitFooError> {
+        if container_count(self, MAX_TREEMAP_CONTAINERS) > MAX_TREEMAP_CONTAINERS {
+            return Err(BitFooError::TooLarge(MAX_TREEMAP_CONTAINERS));
+        }
+
+        let containers = containers(self);
+        let mut buckets: Vec<&[Container]> = Vec::new();
+        let mut rest = &containers[..];
+        while let Some(first) = rest.first() {
+            let high = first.key >> 16;
+            let len = rest.iter().take_while(|c| c.key >> 16 == high).count();
+            buckets.push(&rest[..len]);
+            rest = &rest[len..];
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&(buckets.len() as u64).to_le_bytes());
+        for bucket in buckets {
+            let high = (bucket[0].key >> 16) as u32;
+            out.extend_from_slice(&high.to_le_bytes());
+            write_bitmap(&mut out, bucket);
+        }
+        Ok(out)
+    }
+
+    // Read a 64-bit Roaring treemap serialization
+    pub fn from_roaring_treemap_bytes(bytes: &[u8]) -> Result<Self, BitFooError> {
+        let mut bv = BitFooVec::new();
+        let mut input = bytes;
+        let count = read_u64(&mut input)?;
+
+        let mut prev_high = None;
+        for _ in 0..count {
+            let high = read_u32(&mut input)?;
+            if prev_high >= Some(high) {
+                return Err(BitFooError::Corrupt("treemap keys out of order"));
+            }
+            prev_high = Some(high);
+            read_bitmap(&mut input, high as u64, &mut bv)?;
+        }
+        finish(input, bv)
+    }
+}
+
+// Set bits of a container, keyed by bitno >> 16
+struct Container {
+    key: u64,
+    ranges: Vec<(u16, u16)>,   // Inclusive, relative to the container
+}
+
+// Container encodings, picked by smallest size
+#[derive(PartialEq)]
+enum Kind {
+    Array,
+    Bitmap,
+    Run,
+}
+
+impl Container {
+    fn cardinality(&self) -> u32 {
+        self.ranges.iter().map(|(lo, hi)| (hi - lo) as u32 + 1).sum()
+    }
+
+    fn kind(&self) -> Kind {
+        let cardinality = self.cardinality();
+        let run_size = 2 + 4 * self.ranges.len() as u32;
+        let other_size = match cardinality {
+            0..=MAX_ARRAY => 2 * cardinality,
+            _ => 8 * BITMAP_WORDS as u32,
+        };
+
+        if run_size < other_size {
+            Kind::Run
+        }
+        else if cardinality <= MAX_ARRAY {
+            Kind::Array
+        }
+        else {
+            Kind::Bitmap
+        }
+    }
+
+    fn size(&self, kind: &Kind) -> usize {
+        match kind {
+            Kind::Array => 2 * self.cardinality() as usize,
+            Kind::Bitmap => 8 * BITMAP_WORDS,
+            Kind::Run => 2 + 4 * self.ranges.len(),
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>, kind: &Kind) {
+        match kind {
+            Kind::Array => {
+                for (lo, hi) in &self.ranges {
+                    for value in *lo..=*hi {
+                        out.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            },
+            Kind::Bitmap => {
+                let mut words = vec![0_u64; BITMAP_WORDS];
+                for (lo, hi) in &self.ranges {
+                    for value in *lo..=*hi {
+                        words[value as usize / 64] |= 1 << (value % 64);
+                    }
+                }
+                for word in words {
+                    out.extend_from_slice(&word.to_le_bytes());
+                }
+            },
+            Kind::Run => {
+                out.extend_from_slice(&(self.ranges.len() as u16).to_le_bytes());
+                for (lo, hi) in &self.ranges {
+                    out.extend_from_slice(&lo.to_le_bytes());
+                    out.extend_from_slice(&(hi - lo).to_le_bytes());
+                }
+            },
+        }
+    }
+}
+
+// Return the number of containers needed for our ranges,
+// stopping once past the limit
+fn container_count(bv: &BitFooVec, limit: u64) -> u64 {
+    let mut count = 0;
+    let mut prev_key = None;
+    for range in bv.ranges() {
+        let (first, last) = (range.start() >> 16, range.end() >> 16);
+        count += last - first + 1;
+        if prev_key == Some(first) {
+            count -= 1;
+        }
+        if count > limit {
+            break;
+        }
+        prev_key = Some(last);
+    }
+    count
+}
+
+// Split our ranges at container boundaries
+fn containers(bv: &BitFooVec) -> Vec<Container> {
+    let mut containers: Vec<Container> = Vec::new();
+    for range in bv.ranges() {
+        let mut start = *range.start();
+        loop {
+            let end = (start | 0xffff).min(*range.end());
+            let key = start >> 16;
+            let part = (start as u16, end as u16);
+
+            match containers.last_mut() {
+                Some(container) if container.key == key => {
+                    container.ranges.push(part);
+                },
+                _ => containers.push(Container { key, ranges: vec![part] }),
+            }
+
+            if end == *range.end() {
+                break;
+            }
+            start = end + 1;
+        }
+    }
+    containers
+}
+
+// Append a 32-bit bitmap holding these containers
+fn write_bitmap(out: &mut Vec<u8>, containers: &[Container]) {
+    let kinds: Vec<Kind> = containers.iter().map(|c| c.kind()).collect();
+    let count = containers.len();
+    let has_run = kinds.contains(&Kind::Run);
+    let start = out.len();
+
+    if has_run {
+        let cookie = RUN_COOKIE | ((count as u32 - 1) << 16);
+        out.extend_from_slice(&cookie.to_le_bytes());
+
+        let mut run_bitset = vec![0_u8; count.div_ceil(8)];
+        for (i, kind) in kinds.iter().enumerate() {
+            if *kind == Kind::Run {
+                run_bitset[i / 8] |= 1 << (i % 8);
+            }
+        }
+        out.extend_from_slice(&run_bitset);
+    }
+    else {
+        out.extend_from_slice(&NO_RUN_COOKIE.to_le_bytes());
+        out.extend_from_slice(&(count as u32).to_le_bytes());
+    }
+
+    for container in containers {
+        let cardinality = container.cardinality() - 1;
+        out.extend_from_slice(&(container.key as u16).to_le_bytes());
+        out.extend_from_slice(&(cardinality as u16).to_le_bytes());
+    }
+
+    if !has_run || count >= NO_OFFSET_THRESHOLD {
+        // Offsets are from the start of this bitmap
+        let mut offset = out.len() - start + 4 * count;
+        for (container, kind) in containers.iter().zip(&kinds) {
+            out.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += container.size(kind);
+        }
+    }
+
+    for (container, kind) in containers.iter().zip(&kinds) {
+        container.write(out, kind);
+    }
+}
+
+// Read a 32-bit bitmap, setting its bits under the high key
+fn read_bitmap(input: &mut &[u8], high: u64, bv: &mut BitFooVec)
+    -> Result<(), BitFooError>
+{
+    let cookie = read_u32(input)?;
+    let (count, run_bitset) = if cookie & 0xffff == RUN_COOKIE {
+        let count = (cookie >> 16) as usize + 1;
+        let mut run_bitset = vec![0; count.div_ceil(8)];
+        input.read_exact(&mut run_bitset)?;
+        (count, Some(run_bitset))
+    }
+    else if cookie == NO_RUN_COOKIE {
+        let count = read_u32(input)? as usize;
+        if count > MAX_CONTAINERS {
+            return Err(BitFooError::Corrupt("too many roaring containers"));
+        }
+        (count, None)
+    }
+    else {
+        return Err(BitFooError::BadMagic);
+    };
+
+    let mut headers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let key = read_u16(input)?;
+        let cardinality = read_u16(input)? as u32 + 1;
+        if let Some((prev_key, _)) = headers.last() {
+            if *prev_key >= key {
+                return Err(BitFooError::Corrupt("roaring keys out of order"));
+            }
+        }
+        headers.push((key, cardinality));
+    }
+
+    // Containers are contiguous, so the offsets aren't needed
+    let has_run = run_bitset.is_some();
+    if !has_run || count >= NO_OFFSET_THRESHOLD {
+        for _ in 0..count {
+            read_u32(input)?;
+        }
+    }
+
+    for (i, (key, cardinality)) in headers.into_iter().enumerate() {
+        let base = high << 32 | (key as u64) << 16;
+        let is_run = match &run_bitset {
+            Some(run_bitset) => run_bitset[i / 8] & (1 << (i % 8)) != 0,
+            None => false,
+        };
+
+        if is_run {
+            read_runs(input, base, cardinality, bv)?;
+        }
+        else if cardinality <= MAX_ARRAY {
+            read_array(input, base, cardinality, bv)?;
+        }
+        else {
+            read_words(input, base, cardinality, bv)?;
+        }
+    }
+    Ok(())
+}
+
+// Run container - each run becomes a set_range()
+fn read_runs(input: &mut &[u8], base: u64, cardinality: u32, bv: &mut BitFooVec)
+    -> Result<(), BitFooError>
+{
+    let count = read_u16(input)?;
+    let mut runs: Vec<RangeInclusive<u64>> = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let start = read_u16(input)? as u64;
+        let end = start + read_u16(input)? as u64;
+        if end > u16::MAX as u64 {
+            return Err(BitFooError::Corrupt("roaring run past end of container"));
+        }
+        if let Some(prev) = runs.last() {
+            if *prev.end() >= start {
+                return Err(BitFooError::Corrupt("roaring runs overlap"));
+            }
+        }
+        runs.push(start..=end);
+    }
+
+    let total: u64 = runs.iter().map(|run| run.end() - run.start() + 1).sum();
+    if total != cardinality as u64 {
+        return Err(BitFooError::Corrupt("roaring cardinality mismatch"));
+    }
+    for run in runs {
+        bv.set_range(base + run.start()..=base + run.end());
+    }
+    Ok(())
+}
+
+// Array container - values are gathered into leaf words
+fn read_array(input: &mut &[u8], base: u64, cardinality: u32, bv: &mut BitFooVec)
+    -> Result<(), BitFooError>
+{
+    let mut words = vec![0_u64; BITMAP_WORDS];
+    let mut prev = None;
+    for _ in 0..cardinality {
+        let value = read_u16(input)?;
+        if prev >= Some(value) {
+            return Err(BitFooError::Corrupt("roaring array out of order"));
+        }
+        prev = Some(value);
+        words[value as usize / 64] |= 1 << (value % 64);
+    }
+    set_words(bv, base, &words);
+    Ok(())
+}
+
+// Bitmap container - the words map straight onto leaf words
+fn read_words(input: &mut &[u8], base: u64, cardinality: u32, bv: &mut BitFooVec)
+    -> Result<(), BitFooError>
+{
+    let mut words = vec![0_u64; BITMAP_WORDS];
+    for word in words.iter_mut() {
+        *word = read_u64(input)?;
+    }
+
+    let total: u32 = words.iter().map(|word| word.count_ones()).sum();
+    if total != cardinality {
+        return Err(BitFooError::Corrupt("roaring cardinality mismatch"));
+    }
+    set_words(bv, base, &words);
+    Ok(())
+}
+
+fn set_words(bv: &mut BitFooVec, base: u64, words: &[u64]) {
+    for (i, word) in words.iter().enumerate() {
+        bv.set_word(base + 64 * i as u64, *word);
+    }
+}
+
+// All of the input must have been used
+fn finish(input: &[u8], bv: BitFooVec) -> Result<BitFooVec, BitFooError> {
+    match input.is_empty() {
+        true => Ok(bv),
+        false => Err(BitFooError::Corrupt("trailing bytes after roaring data")),
+    }
+}
+
+fn read_u16(input: &mut &[u8]) -> Result<u16, BitFooError> {
+    let mut buf = [0; 2];
+    input.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(input: &mut &[u8]) -> Result<u32, BitFooError> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(input: &mut &[u8]) -> Result<u64, BitFooError> {
+    let mut buf = [0; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+#[path = "./tests/roaring_test.rs"]
+mod tests;
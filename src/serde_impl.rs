@@ -0,0 +1,139 @@
+use crate::{BitFooVec,BitVec256,Addr};
+use serde::{Serialize,Serializer,Deserialize,Deserializer};
+use serde::de::{self,SeqAccess,Visitor};
+use serde::ser::SerializeSeq;
+use std::fmt;
+
+// Serde support (feature = "serde"). Human readable formats get
+// a compact form (ranges, bit numbers), while binary formats get
+// the dense tree encoding from codec.rs. Deserialized trees go
+// through BitFooVec::read_from() so the Node/KeyIndex invariants
+// are checked before we hand them out.
+
+// BitFooVec interface
+impl Serialize for BitFooVec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            // List of [start, end] inclusive ranges
+            let mut seq = serializer.serialize_seq(None)?;
+            for range in self.ranges() {
+                seq.serialize_element(&(*range.start(), *range.end()))?;
+            }
+            seq.end()
+        }
+        else {
+            let mut bytes = Vec::new();
+            self.write_to(&mut bytes).map_err(serde::ser::Error::custom)?;
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BitFooVec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(RangesVisitor)
+        }
+        else {
+            deserializer.deserialize_bytes(TreeVisitor)
+        }
+    }
+}
+
+// Builds a vector from a list of [start, end] ranges
+struct RangesVisitor;
+
+impl<'de> Visitor<'de> for RangesVisitor {
+    type Value = BitFooVec;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a list of [start, end] bit ranges")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BitFooVec, A::Error> {
+        let mut bv = BitFooVec::new();
+        while let Some((start, end)) = seq.next_element::<(u64, u64)>()? {
+            if start > end {
+                return Err(de::Error::custom(
+                    format!("range start {} is after end {}", start, end)));
+            }
+            bv.set_range(start..=end);
+        }
+        Ok(bv)
+    }
+}
+
+// Rebuilds a vector from the binary tree encoding
+struct TreeVisitor;
+
+impl<'de> Visitor<'de> for TreeVisitor {
+    type Value = BitFooVec;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a serialized BitFooVec tree")
+    }
+
+    fn visit_bytes<E: de::Error>(self, mut bytes: &[u8]) -> Result<BitFooVec, E> {
+        let bv = BitFooVec::read_from(&mut bytes).map_err(E::custom)?;
+        match bytes.is_empty() {
+            true => Ok(bv),
+            false => Err(E::custom("trailing bytes after BitFooVec tree")),
+        }
+    }
+
+    // Some formats hand over bytes as a sequence of u8
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BitFooVec, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+// BitVec256 interface. Human readable formats get the list
+// of set bits, and binary formats the four raw words.
+impl Serialize for BitVec256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        }
+        else {
+            let data = [self.raw_data(0), self.raw_data(1), 
+                self.raw_data(2), self.raw_data(3)];
+            data.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BitVec256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let mut vec = BitVec256::new();
+            for bitno in Vec::<u8>::deserialize(deserializer)? {
+                vec.set(bitno);
+            }
+            Ok(vec)
+        }
+        else {
+            Ok(BitVec256::from_raw_data(<[u64;4]>::deserialize(deserializer)?))
+        }
+    }
+}
+
+// Addr interface. The keys and level all follow from the bitno.
+impl Serialize for Addr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.bitno())
+    }
+}
+
+impl<'de> Deserialize<'de> for Addr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Addr::new(u64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/serde_test.rs"]
+mod tests;
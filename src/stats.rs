@@ -0,0 +1,53 @@
+use std::fmt;
+
+// Shape and memory use of a BitFooVec tree. Per level counts
+// are indexed by node level (1-8).
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct BitFooStats {
+    pub root_level: u8,
+    pub nodes: [u64;10],        // Nodes at each level
+    pub runs: [u64;10],         // Runs held by the nodes at each level
+    pub leaf_words: u64,        // u64s in Content::Bits vectors
+    pub heap_size: usize,       // Bytes allocated by the tree
+    pub wasted_capacity: usize, // Bytes of unused Vec capacity
+}
+
+impl BitFooStats {
+    // Return the total number of nodes
+    pub fn node_count(&self) -> u64 {
+        self.nodes.iter().sum()
+    }
+
+    // Return the total number of runs
+    pub fn run_count(&self) -> u64 {
+        self.runs.iter().sum()
+    }
+
+    // Return the average fraction of the 256 keys in level 1
+    // nodes that hold a leaf word
+    pub fn average_fill(&self) -> f64 {
+        match self.nodes[1] {
+            0 => 0.0,
+            nodes => self.leaf_words as f64 / (nodes as f64 * 256.0),
+        }
+    }
+}
+
+// Display interface
+impl fmt::Display for BitFooStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "root level: {}", self.root_level)?;
+        for level in (1..=self.root_level as usize).rev() {
+            writeln!(f, "level {}: {} nodes, {} runs", 
+                     level, self.nodes[level], self.runs[level])?;
+        }
+        writeln!(f, "leaf words: {} ({:.1}% fill)", 
+                 self.leaf_words, self.average_fill() * 100.0)?;
+        write!(f, "heap size: {} bytes ({} wasted)", 
+               self.heap_size, self.wasted_capacity)
+    }
+}
+
+#[cfg(test)]
+#[path = "./tests/stats_test.rs"]
+mod tests;
@@ -1,5 +1,5 @@
 #[cfg(test)]
-use crate::Addr;
+use crate::{Addr,BitFooError};
 
 #[test]
 fn addr_to_bitno() {
@@ -69,3 +69,37 @@ fn keys_and_level() {
         }
     }
 }
+
+#[test]
+fn set_raises_level() {
+    // Setting a key above our level moves the Addr up
+    let mut addr = Addr::new(0);
+    addr.set(5, 0);
+    assert_eq!(addr.node_level, 1);
+    addr.set(5, 0x12);
+    assert_eq!(addr.node_level, 5);
+    assert_eq!(addr.bitno(), 0x12 << Addr::offset(5));
+}
+
+#[test]
+fn from_keys() {
+    let addr = Addr::from_keys(3, [5, 1, 0x12, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(addr.bitno(), 5 + (1 << 6) + (0x12 << 14));
+    assert_eq!(addr.node_level, 2);
+
+    // Leading zero keys drop the level
+    let addr = Addr::from_keys(8, [5, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(addr.node_level, 1);
+    assert_eq!(Addr::from_keys(8, [0x3f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 3, 0])
+               .bitno(), u64::MAX);
+
+    let keys = [0; 10];
+    assert!(matches!(Addr::try_from_keys(0, keys), Err(BitFooError::BadLevel(0))));
+    assert!(matches!(Addr::try_from_keys(9, keys), Err(BitFooError::BadLevel(9))));
+    assert!(matches!(Addr::try_from_keys(2, [0x40, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+                     Err(BitFooError::BadKey { level: 0, key: 0x40 })));
+    assert!(matches!(Addr::try_from_keys(8, [0, 0, 0, 0, 0, 0, 0, 0, 4, 0]),
+                     Err(BitFooError::BadKey { level: 8, key: 4 })));
+    assert!(matches!(Addr::try_from_keys(2, [0, 0, 0, 1, 0, 0, 0, 0, 0, 0]),
+                     Err(BitFooError::BadKey { level: 3, key: 1 })));
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+use crate::BitFooAllocator;
+
+#[test]
+fn allocate_and_free() {
+    let mut alloc = BitFooAllocator::new();
+    for id in 0..1000 {
+        assert_eq!(alloc.allocate(), Some(id));
+    }
+    assert_eq!(alloc.count(), 1000);
+
+    // Freed ids are handed out again lowest first
+    assert!(alloc.free(500));
+    assert!(alloc.free(10));
+    assert!(!alloc.free(10));
+    assert!(!alloc.is_allocated(10));
+    assert_eq!(alloc.allocate(), Some(10));
+    assert_eq!(alloc.allocate(), Some(500));
+    assert_eq!(alloc.allocate(), Some(1000));
+}
+
+#[test]
+fn allocate_at_least() {
+    let mut alloc = BitFooAllocator::new();
+    assert_eq!(alloc.allocate_at_least(1 << 40), Some(1 << 40));
+    assert_eq!(alloc.allocate_at_least(1 << 40), Some((1 << 40) + 1));
+    assert_eq!(alloc.allocate_at_least(u64::MAX), Some(u64::MAX));
+    assert_eq!(alloc.allocate_at_least(u64::MAX), None);
+    assert_eq!(alloc.allocate(), Some(0));
+}
+
+#[test]
+fn allocate_range() {
+    let mut alloc = BitFooAllocator::new();
+    assert_eq!(alloc.allocate_range(0), None);
+    assert_eq!(alloc.allocate_range(100), Some(0..=99));
+    assert_eq!(alloc.allocate_at_least(150), Some(150));
+
+    // Gap 100-149 is too small for 60, but fits 50
+    assert_eq!(alloc.allocate_range(60), Some(151..=210));
+    assert_eq!(alloc.allocate_range(50), Some(100..=149));
+    assert_eq!(alloc.allocate(), Some(211));
+
+    // Large blocks are stored as runs
+    let block = alloc.allocate_range(1 << 40).unwrap();
+    assert_eq!(block, 212..=211 + (1 << 40));
+    assert_eq!(alloc.count(), 212 + (1 << 40));
+    assert_eq!(alloc.allocate(), Some(212 + (1 << 40)));
+
+    alloc.free_range(1000..=1999);
+    assert_eq!(alloc.allocate_range(1000), Some(1000..=1999));
+}
+
+#[test]
+fn allocate_full() {
+    // Skips over a nearly full space to the last free id
+    let mut alloc = BitFooAllocator::new();
+    assert_eq!(alloc.allocate_range(u64::MAX), Some(0..=u64::MAX - 1));
+    assert_eq!(alloc.allocate_range(2), None);
+    assert_eq!(alloc.allocate(), Some(u64::MAX));
+    assert_eq!(alloc.allocate(), None);
+    assert_eq!(alloc.count(), 1 << 64);
+}
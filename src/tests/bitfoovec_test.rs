@@ -1,11 +1,17 @@
 #[cfg(test)]
-use crate::BitFooVec;
+use crate::{BitFooVec,BitFooError,Addr};
+#[cfg(test)]
+use crate::test_helpers::{sample_vec,from_bits,to_bytes};
+#[cfg(test)]
+use std::ops::RangeInclusive;
+#[cfg(test)]
+use std::cmp::Ordering;
 
 #[test]
 fn sizeof() {
     let size = std::mem::size_of::<BitFooVec>();
     println!("Size of BitFooVec is {} bytes", size);
-    assert_eq!(size, 104);
+    assert_eq!(size, 112);
 }
 
 #[test]
@@ -22,3 +28,675 @@ fn set_and_get() {
         assert_eq!(bv.get(b), bits.contains(&b));
     }
 }
+
+#[test]
+fn iter_sparse() {
+    // Bits at the boundaries of every level up to u64::MAX
+    let mut bv = BitFooVec::new();
+    let mut bits = vec![0_u64, 62, 63, 64, 127];
+    for shift in (14..64).step_by(8) {
+        bits.push((1 << shift) - 1);
+        bits.push(1 << shift);
+    }
+    bits.push(u64::MAX - 1);
+    bits.push(u64::MAX);
+
+    for b in &bits {
+        bv.set(*b);
+    }
+    assert_eq!(bv.level(), 8);
+
+    let out_bits: Vec<u64> = bv.iter().collect();
+    assert_eq!(bits, out_bits);
+}
+
+#[test]
+fn iter_runs() {
+    // Runs at level 1 (64 bits) and level 2 (16384 bits) 
+    // bordered by single bits
+    let mut bv = BitFooVec::new();
+    let mut bits = vec![5_u64];
+    bits.extend(64..64*3);
+    bits.extend(16384..16384*2);
+    bits.push(16384*2 + 1);
+
+    for b in &bits {
+        bv.set(*b);
+    }
+
+    let mut out_bits = Vec::new();
+    for b in &bv {
+        out_bits.push(b);
+    }
+    assert_eq!(bits, out_bits);
+}
+
+#[test]
+fn iter_run_at_end() {
+    // A run containing u64::MAX must not overflow
+    let mut bv = BitFooVec::new();
+    for b in u64::MAX-63..=u64::MAX {
+        bv.set(b);
+    }
+    assert_eq!(bv.iter().count(), 64);
+    assert_eq!(bv.iter().next_back(), Some(u64::MAX));
+}
+
+#[test]
+fn iter_empty() {
+    let bv = BitFooVec::new();
+    assert_eq!(bv.iter().next(), None);
+}
+
+#[test]
+fn range() {
+    let (bv, bits) = sample_vec();
+    let bounds = [0_u64, 1, 3, 4, 64, 100, 128, 200, 16384*3 + 7, 
+        16384*4, 1 << 40, (1 << 40) + 64, u64::MAX - 1, u64::MAX];
+
+    for start in &bounds {
+        for end in &bounds {
+            let expected: Vec<u64> = bits.iter().cloned()
+                .filter(|b| b >= start && b < end).collect();
+            let out: Vec<u64> = bv.range(*start..*end).collect();
+            assert_eq!(expected, out, "range {}..{}", start, end);
+
+            let expected: Vec<u64> = bits.iter().cloned()
+                .filter(|b| b >= start && b <= end).collect();
+            let out: Vec<u64> = bv.range(*start..=*end).collect();
+            assert_eq!(expected, out, "range {}..={}", start, end);
+            assert!(bv.iter_range(*start..=*end).eq(out));
+        }
+    }
+}
+
+#[test]
+fn iter_from() {
+    let (bv, bits) = sample_vec();
+    for start in &[0_u64, 101, 16384*3 + 100, 1 << 40, u64::MAX] {
+        let expected: Vec<u64> = bits.iter().cloned()
+            .filter(|b| b >= start).collect();
+        let out: Vec<u64> = bv.iter_from(*start).collect();
+        assert_eq!(expected, out);
+    }
+    assert_eq!(bv.range(..).count(), bits.len());
+}
+
+#[test]
+fn range_beyond_root() {
+    // Root is only level 1, so the start is past anything set
+    let mut bv = BitFooVec::new();
+    bv.set(5);
+    assert_eq!(bv.range(1 << 20..).next(), None);
+    assert_eq!(bv.range(0..5).next(), None);
+    assert_eq!(bv.range(5..=5).next(), Some(5));
+}
+
+#[test]
+fn iter_rev() {
+    let (bv, bits) = sample_vec();
+    let expected: Vec<u64> = bits.iter().rev().cloned().collect();
+    let out: Vec<u64> = bv.iter().rev().collect();
+    assert_eq!(expected, out);
+
+    // Reverse over a partial range, starting mid-run
+    let expected: Vec<u64> = bits.iter().rev().cloned()
+        .filter(|b| *b >= 100 && *b <= 16384*3 + 10).collect();
+    let out: Vec<u64> = bv.range(100..=16384*3 + 10).rev().collect();
+    assert_eq!(expected, out);
+}
+
+#[test]
+fn iter_double_ended() {
+    // Alternate between the two ends - they must meet
+    // without skipping or repeating any bits
+    let (bv, bits) = sample_vec();
+    let mut iter = bv.iter();
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+
+    while let Some(b) = iter.next() {
+        front.push(b);
+        match iter.next_back() {
+            Some(b) => back.push(b),
+            None => break,
+        }
+    }
+    back.reverse();
+    front.extend(back);
+    assert_eq!(bits, front);
+}
+
+#[test]
+fn ranges() {
+    // Adjacent runs and partial words merge into single intervals
+    let mut bv = BitFooVec::new();
+    let mut bits = vec![3_u64, 4, 5, 63];
+    bits.extend(64..64*3 + 2);      // Word, run and part of next word
+    bits.extend(16384-1..16384*2);  // Crosses into a level 2 run
+    bits.extend(&[1 << 40, u64::MAX - 1, u64::MAX]);
+    for b in &bits {
+        bv.set(*b);
+    }
+
+    let out: Vec<RangeInclusive<u64>> = bv.ranges().collect();
+    assert_eq!(out, vec![3..=5, 63..=64*3 + 1, 16384-1..=16384*2-1,
+        1 << 40..=1 << 40, u64::MAX-1..=u64::MAX]);
+
+    // Expanding the intervals gives back the bits
+    let expanded: Vec<u64> = out.into_iter().flatten().collect();
+    assert_eq!(bits, expanded);
+    assert_eq!(BitFooVec::new().ranges().next(), None);
+}
+
+#[test]
+fn count() {
+    let (bv, bits) = sample_vec();
+    assert_eq!(bv.count(), bits.len() as u128);
+    assert_eq!(BitFooVec::new().count(), 0);
+}
+
+#[test]
+fn count_range() {
+    let (bv, bits) = sample_vec();
+    let bounds = [0_u64, 1, 3, 4, 64, 100, 128, 200, 16384*3 + 7, 
+        16384*4, 1 << 40, (1 << 40) + 64, u64::MAX - 1, u64::MAX];
+
+    for start in &bounds {
+        for end in &bounds {
+            let expected = bits.iter()
+                .filter(|b| *b >= start && *b <= end).count() as u128;
+            assert_eq!(bv.count_range(*start..=*end), expected, 
+                "range {}..={}", start, end);
+        }
+    }
+    assert_eq!(bv.count_range(..), bits.len() as u128);
+
+    // Range beyond the root level
+    let mut bv = BitFooVec::new();
+    bv.set(5);
+    assert_eq!(bv.count_range(1 << 20..), 0);
+    assert_eq!(bv.count_range(0..=5), 1);
+}
+
+#[test]
+fn rank() {
+    let (bv, bits) = sample_vec();
+    for bitno in &[0_u64, 3, 4, 64, 65, 127, 128, 192, 200, 256, 
+            16384*3 + 77, 16384*4, 1 << 40, (1 << 40) + 64, u64::MAX] {
+        let expected = bits.iter().filter(|b| *b < bitno).count() as u64;
+        assert_eq!(bv.rank(*bitno), expected, "rank({})", bitno);
+    }
+
+    // Bit beyond the root level
+    let mut bv = BitFooVec::new();
+    bv.set(5);
+    assert_eq!(bv.rank(1 << 30), 1);
+}
+
+#[test]
+fn select() {
+    let (bv, bits) = sample_vec();
+    for (k, bitno) in bits.iter().enumerate() {
+        assert_eq!(bv.select(k as u64), Some(*bitno));
+        assert_eq!(bv.rank(*bitno), k as u64);
+    }
+    assert_eq!(bv.select(bits.len() as u64), None);
+    assert_eq!(BitFooVec::new().select(0), None);
+}
+
+#[test]
+fn set_algebra() {
+    use std::collections::BTreeSet;
+
+    // Overlapping runs and single bits at different root levels
+    let (a, a_bits) = sample_vec();
+    let mut b_bits: Vec<u64> = vec![0, 3, 100, 101, 16384*3 + 5, 1 << 40];
+    b_bits.extend(0..64);
+    b_bits.extend(16384*2..16384*3 + 200);
+    let b = from_bits(&b_bits);
+    let c = from_bits(&[5, 64, 127, 30000]);
+    assert_ne!(a.level(), c.level());
+
+    for (x, x_bits, y, y_bits) in &[(&a, &a_bits, &b, &b_bits), 
+            (&a, &a_bits, &c, &vec![5, 64, 127, 30000])] {
+        let xs: BTreeSet<u64> = x_bits.iter().cloned().collect();
+        let ys: BTreeSet<u64> = y_bits.iter().cloned().collect();
+
+        let and: Vec<u64> = xs.intersection(&ys).cloned().collect();
+        let or: Vec<u64> = xs.union(&ys).cloned().collect();
+        let xor: Vec<u64> = xs.symmetric_difference(&ys).cloned().collect();
+        let sub: Vec<u64> = xs.difference(&ys).cloned().collect();
+        let rsub: Vec<u64> = ys.difference(&xs).cloned().collect();
+
+        assert_eq!((*x & *y).iter().collect::<Vec<u64>>(), and);
+        assert_eq!((*y & *x).iter().collect::<Vec<u64>>(), and);
+        assert_eq!((*x | *y).iter().collect::<Vec<u64>>(), or);
+        assert_eq!((*y | *x).iter().collect::<Vec<u64>>(), or);
+        assert_eq!((*x ^ *y).iter().collect::<Vec<u64>>(), xor);
+        assert_eq!((*y ^ *x).iter().collect::<Vec<u64>>(), xor);
+        assert_eq!((*x - *y).iter().collect::<Vec<u64>>(), sub);
+        assert_eq!((*y - *x).iter().collect::<Vec<u64>>(), rsub);
+    }
+}
+
+#[test]
+fn set_algebra_assign() {
+    let (a, a_bits) = sample_vec();
+    let b = from_bits(&[3, 4, 64, u64::MAX]);
+
+    let mut v = a.clone();
+    v |= &b;
+    v -= b.clone();
+    let expected: Vec<u64> = a_bits.iter().cloned()
+        .filter(|bit| ![3, 64, u64::MAX].contains(bit)).collect();
+    assert_eq!(v.iter().collect::<Vec<u64>>(), expected);
+
+    v ^= &a;
+    assert_eq!(v.iter().collect::<Vec<u64>>(), vec![3, 64, u64::MAX]);
+    v &= b;
+    assert_eq!(v.iter().collect::<Vec<u64>>(), vec![3, 64, u64::MAX]);
+}
+
+#[test]
+fn set_algebra_runs() {
+    // Union of two half words is detected as a run
+    let a = from_bits(&(0..32).collect::<Vec<u64>>());
+    let b = from_bits(&(32..64).collect::<Vec<u64>>());
+    let c = &a | &b;
+    assert!(c.root_node.index.is_run(&Addr::new(0)));
+    assert_eq!(c.count(), 64);
+
+    // A level 2 run minus a bit splits back into nodes
+    let full = from_bits(&(0..16384).collect::<Vec<u64>>());
+    assert!(full.root_node.index.is_all_runs());
+    let d = &full - &from_bits(&[100]);
+    assert_eq!(d.count(), 16383);
+    assert!(!d.get(100));
+
+    // Xor of a run with a node flips the node
+    let e = &full ^ &from_bits(&[5, 20000]);
+    assert_eq!(e.count(), 16384);
+    assert!(!e.get(5));
+    assert!(e.get(20000));
+}
+
+#[test]
+fn clear() {
+    // Clearing one bit leaves the rest of its child node alone
+    let (mut bv, mut bits) = sample_vec();
+    for b in &[3_u64, 200, 16384*3 + 5, (1 << 40) + 63] {
+        bv.clear(*b);
+        bits.retain(|bit| bit != b);
+        assert!(!bv.get(*b));
+    }
+    assert_eq!(bv.iter().collect::<Vec<u64>>(), bits);
+
+    // Clear everything
+    for b in &bits {
+        bv.clear(*b);
+    }
+    assert_eq!(bv.count(), 0);
+    assert_eq!(bv.iter().next(), None);
+}
+
+#[test]
+fn set_and_clear_range() {
+    use std::collections::BTreeSet;
+    let ranges = [(0_u64, 0_u64), (5, 70), (64, 127), (100, 16384*2 + 3),
+        (16383, 16384), (16384*5 - 1, 16384*9 + 64)];
+
+    for (set_start, set_end) in &ranges {
+        for (clear_start, clear_end) in &ranges {
+            let (mut bv, bits) = sample_vec();
+            let mut expected: BTreeSet<u64> = bits.iter().cloned().collect();
+
+            bv.set_range(*set_start..=*set_end);
+            expected.extend(*set_start..=*set_end);
+            assert_eq!(bv.iter().collect::<BTreeSet<u64>>(), expected);
+
+            bv.clear_range(*clear_start..=*clear_end);
+            for b in *clear_start..=*clear_end {
+                expected.remove(&b);
+            }
+            assert_eq!(bv.iter().collect::<BTreeSet<u64>>(), expected);
+            assert_eq!(bv.count(), expected.len() as u128);
+        }
+    }
+}
+
+#[test]
+fn set_and_clear_large_range() {
+    // A billion bits is only a handful of runs
+    let mut bv = BitFooVec::new();
+    bv.set_range(1 << 20..(1 << 20) + 1_000_000_000);
+    assert_eq!(bv.count(), 1_000_000_000);
+    assert_eq!(bv.ranges().collect::<Vec<_>>(), 
+        vec![1 << 20..=(1 << 20) + 999_999_999]);
+
+    // Clearing splits the runs
+    bv.clear_range(1 << 29..(1 << 29) + 10);
+    assert_eq!(bv.count(), 1_000_000_000 - 10);
+    assert_eq!(bv.ranges().collect::<Vec<_>>(), vec![
+        1 << 20..=(1 << 29) - 1,
+        (1 << 29) + 10..=(1 << 20) + 999_999_999]);
+
+    // Every u64
+    let mut bv = BitFooVec::new();
+    bv.set_range(..);
+    assert_eq!(bv.count(), 1 << 64);
+    bv.clear_range(1..u64::MAX);
+    assert_eq!(bv.iter().collect::<Vec<u64>>(), vec![0, u64::MAX]);
+
+    // Clearing above the root is a no-op
+    let mut bv = BitFooVec::new();
+    bv.set(5);
+    bv.clear_range(1 << 40..);
+    assert_eq!(bv.count(), 1);
+}
+
+#[test]
+fn next_and_prev() {
+    use std::collections::BTreeSet;
+    let (bv, bits) = sample_vec();
+    let set: BTreeSet<u64> = bits.iter().cloned().collect();
+
+    let mut positions: Vec<u64> = (0..300).collect();
+    positions.extend((16384*3 - 70..16384*4 + 70).step_by(61));
+    positions.extend((1 << 40) - 70..(1 << 40) + 140);
+    positions.extend(u64::MAX - 70..=u64::MAX);
+
+    for n in &positions {
+        let n = *n;
+        assert_eq!(bv.next_set(n), set.range(n..).next().cloned());
+        assert_eq!(bv.prev_set(n), set.range(..=n).next_back().cloned());
+
+        // Brute force the clear bits with get()
+        let mut next_clear = Some(n);
+        while let Some(b) = next_clear {
+            if !set.contains(&b) { break; }
+            next_clear = b.checked_add(1);
+        }
+        let mut prev_clear = Some(n);
+        while let Some(b) = prev_clear {
+            if !set.contains(&b) { break; }
+            prev_clear = b.checked_sub(1);
+        }
+        assert_eq!(bv.next_clear(n), next_clear, "next_clear({})", n);
+        assert_eq!(bv.prev_clear(n), prev_clear, "prev_clear({})", n);
+    }
+}
+
+#[test]
+fn next_and_prev_runs() {
+    // Clear bits are found by skipping whole runs
+    let mut bv = BitFooVec::new();
+    bv.set_range(0..1 << 50);
+    assert_eq!(bv.next_clear(0), Some(1 << 50));
+    assert_eq!(bv.prev_clear((1 << 50) - 1), None);
+    assert_eq!(bv.next_set(1 << 50), None);
+    assert_eq!(bv.prev_set(u64::MAX), Some((1 << 50) - 1));
+
+    bv.clear(1 << 45);
+    assert_eq!(bv.next_clear(5), Some(1 << 45));
+    assert_eq!(bv.prev_clear((1 << 50) - 1), Some(1 << 45));
+
+    // Full vector has no clear bits, and an empty one no set bits
+    bv.set_range(..);
+    assert_eq!(bv.next_clear(0), None);
+    assert_eq!(bv.prev_clear(u64::MAX), None);
+    let bv = BitFooVec::new();
+    assert_eq!(bv.next_set(0), None);
+    assert_eq!(bv.prev_set(u64::MAX), None);
+    assert_eq!(bv.next_clear(u64::MAX), Some(u64::MAX));
+}
+
+#[test]
+fn write_and_read() {
+    let (sample, _bits) = sample_vec();
+    let mut full = BitFooVec::new();
+    full.set_range(..);
+    let mut sparse = BitFooVec::new();
+    sparse.set(u64::MAX);
+    sparse.set_range(1 << 30..1 << 33);
+
+    for bv in [BitFooVec::new(), sample, full, sparse].iter() {
+        let mut bytes = Vec::new();
+        bv.write_to(&mut bytes).unwrap();
+        let loaded = BitFooVec::read_from(&bytes[..]).unwrap();
+        assert_eq!(loaded.level(), bv.level());
+        assert_eq!(loaded.count(), bv.count());
+        assert!(loaded.ranges().eq(bv.ranges()));
+    }
+}
+
+#[test]
+fn from_sorted_iter() {
+    let (expected, bits) = sample_vec();
+    let bv = BitFooVec::from_sorted_iter(bits.iter().cloned()).unwrap();
+    assert!(bv.iter().eq(expected.iter()));
+
+    // Builds the same tree as set(), with full words and nodes
+    // merged into runs
+    let mut bits: Vec<u64> = (0..20000).collect();
+    bits.extend((1 << 20..(1 << 20) + 100000).step_by(3));
+    bits.extend(u64::MAX - 70..=u64::MAX);
+    let bv = BitFooVec::from_sorted_iter(bits.iter().cloned()).unwrap();
+    assert_eq!(to_bytes(&bv), to_bytes(&from_bits(&bits)));
+    assert!(bv.ranges().take(2).eq(vec![0..=19999, 1 << 20..=1 << 20]));
+
+    // Repeats are fine, but not going backwards
+    let bv = BitFooVec::from_sorted_iter(vec![1, 1, 5, 5, 5, 64]).unwrap();
+    assert!(bv.iter().eq(vec![1, 5, 64]));
+    assert!(matches!(BitFooVec::from_sorted_iter(vec![1, 7, 6]), 
+        Err(BitFooError::Unsorted(6))));
+    assert!(matches!(BitFooVec::from_sorted_iter(vec![1, 700, 65]), 
+        Err(BitFooError::Unsorted(65))));
+    assert_eq!(BitFooVec::from_sorted_iter(vec![]).unwrap().count(), 0);
+}
+
+#[test]
+fn collect_and_extend() {
+    let (expected, mut bits) = sample_vec();
+    bits.reverse();
+    let bv: BitFooVec = bits.iter().cloned().collect();
+    assert_eq!(to_bytes(&bv), to_bytes(&expected));
+
+    let mut bv = BitFooVec::new();
+    bv.extend(vec![10, 3, 700]);
+    bv.extend(vec![100..200, 5..5]);
+    bv.extend(vec![1 << 40..=(1 << 40) + 1, 150..=250]);
+    assert!(bv.ranges().eq(vec![3..=3, 10..=10, 100..=250, 700..=700, 
+        1 << 40..=(1 << 40) + 1]));
+}
+
+fn hash_of<T: std::hash::Hash>(value: &T) -> u64 {
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn eq_and_hash() {
+    // Same bits set in a different order and via a higher root
+    let mut a = BitFooVec::new();
+    a.set_range(0..200);
+    let mut b = BitFooVec::new();
+    b.set(1 << 40);
+    for bitno in 0..200 {
+        b.set(bitno);
+    }
+    assert_ne!(a, b);
+    b.clear(1 << 40);
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+
+    b.clear(199);
+    assert_ne!(a, b);
+    assert_ne!(BitFooVec::new(), a);
+    assert_eq!(BitFooVec::new(), BitFooVec::default());
+
+    let mut set = std::collections::HashSet::new();
+    set.insert(a.clone());
+    set.insert(b.clone());
+    b.set(199);
+    assert!(!set.insert(b));
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn ord() {
+    // Lexicographic over the sorted bits, so a prefix is less
+    let cases = [
+        (vec![0..=5], vec![0..=5], Ordering::Equal),
+        (vec![], vec![0..=0], Ordering::Less),
+        (vec![0..=5], vec![0..=6], Ordering::Less),
+        (vec![1..=5], vec![0..=100], Ordering::Greater),
+        (vec![0..=5, 8..=8], vec![0..=6], Ordering::Greater),
+        (vec![0..=5, 8..=8], vec![0..=5, 7..=9], Ordering::Greater),
+        (vec![0..=u64::MAX], vec![0..=u64::MAX - 1], Ordering::Greater),
+        (vec![3..=3, 1 << 40..=1 << 40], vec![3..=3, 1 << 41..=1 << 41], Ordering::Less),
+    ];
+
+    for (a, b, expected) in cases.iter() {
+        let a = BitFooVec::from_ranges(a.iter().cloned());
+        let b = BitFooVec::from_ranges(b.iter().cloned());
+        assert_eq!(a.cmp(&b), *expected, "{} vs {}", a, b);
+        assert_eq!(b.cmp(&a), expected.reverse(), "{} vs {}", b, a);
+
+        // Small cases match comparing bit by bit
+        if a.count() < 1000 && b.count() < 1000 {
+            assert_eq!(a.iter().cmp(b.iter()), *expected);
+        }
+    }
+}
+
+#[test]
+fn predicates() {
+    // Check against the set algebra for vectors with runs, single
+    // bits and different root levels
+    let (sample, _bits) = sample_vec();
+    let mut full = BitFooVec::new();
+    full.set_range(..);
+    let vecs = [
+        BitFooVec::new(),
+        full,
+        sample.clone(),
+        &sample - &from_bits(&[u64::MAX, 1 << 40]),
+        from_bits(&[3, 64, 16384 * 3 + 9]),
+        from_bits(&[5, 1 << 40]),
+        BitFooVec::from_ranges(vec![0..=1 << 20]),
+        BitFooVec::from_ranges(vec![0..=255, 16384 * 3..=16384 * 4 - 1]),
+    ];
+
+    for (i, x) in vecs.iter().enumerate() {
+        for (j, y) in vecs.iter().enumerate() {
+            let and = x & y;
+            assert_eq!(x.is_subset(y), and == *x, "{} subset {}", i, j);
+            assert_eq!(x.is_superset(y), and == *y, "{} superset {}", i, j);
+            assert_eq!(x.intersects(y), and.count() > 0, "{} intersects {}", i, j);
+            assert_eq!(x.is_disjoint(y), and.count() == 0, "{} disjoint {}", i, j);
+            assert_eq!(x.intersection_count(y), and.count(), "{} count {}", i, j);
+        }
+    }
+}
+
+#[test]
+fn predicates_levels() {
+    // A high root is compared down its key 0 chain
+    let small = from_bits(&[1, 2, 3]);
+    let mut big = from_bits(&[1, 2, 3, 1 << 50]);
+    assert!(small.is_subset(&big));
+    assert!(!big.is_subset(&small));
+    big.clear(1 << 50);
+    assert!(big.is_subset(&small));
+    assert!(small.is_superset(&big));
+
+    // Run high in the tree covering the whole of a lower root
+    let mut runs = BitFooVec::new();
+    runs.set_range(0..1 << 40);
+    assert!(small.is_subset(&runs));
+    assert!(!runs.is_subset(&small));
+    assert_eq!(runs.intersection_count(&small), 3);
+    assert!(runs.intersects(&small));
+    assert!(runs.is_disjoint(&from_bits(&[1 << 41])));
+}
+
+#[test]
+fn shrink_root() {
+    // Clearing high bits drops the root back down
+    let mut bv = from_bits(&[5, 1 << 20, u64::MAX]);
+    assert_eq!(bv.level(), 8);
+    bv.clear(u64::MAX);
+    assert_eq!(bv.level(), 2);
+    bv.clear_range(1 << 14..);
+    assert_eq!(bv.level(), 1);
+    assert!(bv.iter().eq(vec![5]));
+    bv.clear(5);
+    assert_eq!(bv.level(), 1);
+    assert_eq!(bv.count(), 0);
+
+    // Clearing everything gives a level 1 root
+    let mut bv = from_bits(&[1 << 40, 1 << 50]);
+    bv.clear_range(..);
+    assert_eq!(bv.level(), 1);
+    assert_eq!(bv.to_string(), "{}");
+
+    // A run under key 0 becomes a full root
+    let mut bv = BitFooVec::new();
+    bv.set_range(0..1 << 22);
+    bv.set(1 << 40);
+    bv.clear(1 << 40);
+    assert_eq!(bv.level(), 2);
+    assert_eq!(bv.count(), 1 << 22);
+    assert_eq!(bv.next_clear(0), Some(1 << 22));
+    bv.set(1 << 22);
+    assert_eq!(bv.level(), 3);
+    assert_eq!(bv.count(), (1 << 22) + 1);
+
+    // Set operations shrink their result
+    let a = from_bits(&[7, 1 << 40]);
+    let b = from_bits(&[7, 1 << 50]);
+    assert_eq!((&a & &b).level(), 1);
+    assert_eq!((&a - &a).level(), 1);
+    let mut c = a.clone();
+    c ^= &from_bits(&[1 << 40]);
+    assert_eq!(c.level(), 1);
+    assert!(c.iter().eq(vec![7]));
+}
+
+#[test]
+fn shrink_to_fit() {
+    let mut bv = BitFooVec::new();
+    for bitno in (0..1 << 20).step_by(100) {
+        bv.set(bitno);
+    }
+    bv.clear_range(1000..1 << 19);
+    let before = bv.stats();
+    assert!(before.wasted_capacity > 0);
+
+    bv.shrink_to_fit();
+    let after = bv.stats();
+    assert_eq!(after.wasted_capacity, 0);
+    assert!(after.heap_size < before.heap_size);
+    let expected = (0..1 << 20).step_by(100)
+        .filter(|bitno| *bitno < 1000 || *bitno >= 1 << 19);
+    assert!(bv.iter().eq(expected));
+}
+
+#[test]
+fn validate() {
+    let mut bv = BitFooVec::new();
+    assert!(bv.validate().is_ok());
+    for bitno in &[0, 63, 64, 1 << 20, 1 << 40, u64::MAX] {
+        bv.set(*bitno);
+        assert!(bv.validate().is_ok());
+    }
+    bv.set_range(100..1 << 30);
+    assert!(bv.validate().is_ok());
+    bv.clear_range(0..=u64::MAX - 1);
+    assert!(bv.validate().is_ok());
+    bv.clear(u64::MAX);
+    assert!(bv.validate().is_ok());
+}
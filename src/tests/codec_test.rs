@@ -0,0 +1,143 @@
+#[cfg(test)]
+use crate::{BitFooVec,BitFooError};
+#[cfg(test)]
+use super::Crc32;
+#[cfg(test)]
+use crate::test_helpers::to_bytes;
+
+// Replace the trailing checksum so an edited stream gets
+// past the checksum test
+fn reseal(bytes: &mut Vec<u8>) {
+    let len = bytes.len() - 4;
+    let mut crc = Crc32::new();
+    crc.update(&bytes[..len]);
+    bytes.truncate(len);
+    bytes.extend_from_slice(&crc.finish().to_le_bytes());
+}
+
+#[test]
+fn crc32() {
+    // Standard check value for CRC-32 (IEEE)
+    let mut crc = Crc32::new();
+    crc.update(b"123456789");
+    assert_eq!(crc.finish(), 0xcbf4_3926);
+}
+
+#[test]
+fn layout() {
+    // Level 1 root with one leaf word at key 0
+    let mut bv = BitFooVec::new();
+    bv.set(1);
+    bv.set(3);
+    let bytes = to_bytes(&bv);
+
+    assert_eq!(&bytes[0..4], b"BFOO");
+    assert_eq!(bytes[4], 1); // Version
+    assert_eq!(bytes[5], 1); // Level
+    assert_eq!(bytes[6], 1); // nodes word 0 has key 0 set
+    assert_eq!(&bytes[7..70], &[0; 63][..]);
+    assert_eq!(bytes[70], 0b1010); // Leaf word
+    assert_eq!(bytes.len(), 6 + 64 + 8 + 4);
+}
+
+#[test]
+fn bad_header() {
+    let mut bv = BitFooVec::new();
+    bv.set(100);
+    let bytes = to_bytes(&bv);
+
+    let mut bad = bytes.clone();
+    bad[0] = b'X';
+    assert!(matches!(BitFooVec::read_from(&bad[..]), Err(BitFooError::BadMagic)));
+
+    let mut bad = bytes.clone();
+    bad[4] = 2;
+    assert!(matches!(BitFooVec::read_from(&bad[..]), 
+        Err(BitFooError::UnsupportedVersion(2))));
+
+    for level in [0, 9] {
+        let mut bad = bytes.clone();
+        bad[5] = level;
+        reseal(&mut bad);
+        assert!(matches!(BitFooVec::read_from(&bad[..]), 
+            Err(BitFooError::Corrupt(_))));
+    }
+}
+
+#[test]
+fn damaged() {
+    let mut bv = BitFooVec::new();
+    bv.set_range(1000..=1_000_000);
+    bv.set(1 << 40);
+    let bytes = to_bytes(&bv);
+
+    // Flip a bit in a leaf word
+    let mut bad = bytes.clone();
+    let len = bad.len();
+    bad[len - 10] ^= 0x10;
+    assert!(matches!(BitFooVec::read_from(&bad[..]), 
+        Err(BitFooError::ChecksumMismatch)));
+
+    // Every truncation fails cleanly
+    for len in 0..bytes.len() {
+        assert!(BitFooVec::read_from(&bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn corrupt_tree() {
+    let mut bv = BitFooVec::new();
+    bv.set(1);
+    let bytes = to_bytes(&bv);
+
+    // Key 0 as both node and run
+    let mut bad = bytes.clone();
+    bad[6 + 32] = 1;
+    reseal(&mut bad);
+    assert!(matches!(BitFooVec::read_from(&bad[..]), 
+        Err(BitFooError::Corrupt(_))));
+
+    // Empty and full leaf words
+    for word in [0, u64::MAX] {
+        let mut bad = bytes.clone();
+        bad[70..78].copy_from_slice(&u64::to_le_bytes(word));
+        reseal(&mut bad);
+        assert!(matches!(BitFooVec::read_from(&bad[..]), 
+            Err(BitFooError::Corrupt(_))));
+    }
+
+    // Level 8 roots only have keys 0-3
+    let mut bv = BitFooVec::new();
+    bv.set(u64::MAX - 1);
+    let mut bad = to_bytes(&bv);
+    assert_eq!(bad[5], 8);
+    bad[6 + 32] = 0x10;
+    reseal(&mut bad);
+    assert!(matches!(BitFooVec::read_from(&bad[..]), 
+        Err(BitFooError::Corrupt(_))));
+
+    // Child node with nothing in it
+    let mut bv = BitFooVec::new();
+    bv.set(1 << 20);
+    let mut bad = to_bytes(&bv);
+    assert_eq!(bad[5], 2);
+    let len = bad.len() - 4 - 8;
+    bad.truncate(len);
+    bad[len - 64..].fill(0);
+    bad.extend_from_slice(&[0; 4]);
+    reseal(&mut bad);
+    assert!(matches!(BitFooVec::read_from(&bad[..]), 
+        Err(BitFooError::Corrupt(_))));
+}
+
+#[test]
+fn flushes_writer() {
+    // A buffered writer holds nothing back once write_to returns
+    let mut bv = BitFooVec::new();
+    bv.set_range(0..1000);
+    bv.set(1 << 40);
+
+    let mut writer = std::io::BufWriter::with_capacity(1 << 16, Vec::new());
+    bv.write_to(&mut writer).unwrap();
+    assert_eq!(writer.get_ref(), &to_bytes(&bv));
+}
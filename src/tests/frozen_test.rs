@@ -0,0 +1,129 @@
+#[cfg(test)]
+use crate::{BitFooVec,BitFooError,FrozenBitFooVec};
+#[cfg(test)]
+use crate::test_helpers::sample_with_runs;
+
+#[cfg(test)]
+const POSITIONS: [u64; 16] = [0, 1, 3, 4, 64, 100, 128, 200, 16384 * 3 + 7, 
+    16384 * 4, 1 << 40, (1 << 40) + 64, 1 << 50, (1 << 51) + 5, 
+    u64::MAX - 1, u64::MAX];
+
+#[test]
+fn queries() {
+    let bv = sample_with_runs();
+    let bytes = bv.freeze();
+    let frozen = FrozenBitFooVec::new(&bytes).unwrap();
+
+    assert_eq!(frozen.level(), bv.level());
+    assert_eq!(frozen.count(), bv.count());
+    for bitno in POSITIONS.iter().flat_map(|b| b.saturating_sub(1)..=b.saturating_add(1)) {
+        assert_eq!(frozen.get(bitno), bv.get(bitno), "get({})", bitno);
+        assert_eq!(frozen.rank(bitno), bv.rank(bitno), "rank({})", bitno);
+        assert_eq!(frozen.next_set(bitno), bv.next_set(bitno), "next_set({})", bitno);
+    }
+
+    for start in POSITIONS.iter() {
+        for end in POSITIONS.iter() {
+            assert_eq!(frozen.count_range(start..=end), bv.count_range(start..=end),
+                "count_range({}..={})", start, end);
+            assert!(frozen.range(start..end).take(1000).eq(bv.range(start..end).take(1000)),
+                "range({}..{})", start, end);
+        }
+    }
+}
+
+#[test]
+fn iter() {
+    let mut bv = BitFooVec::new();
+    for bitno in (0..100000).step_by(7) {
+        bv.set(bitno);
+    }
+    bv.set_range(1 << 30..(1 << 30) + 5000);
+    bv.set(u64::MAX);
+
+    let bytes = bv.freeze();
+    let frozen = FrozenBitFooVec::new(&bytes).unwrap();
+    assert!(frozen.iter().eq(bv.iter()));
+    assert_eq!((&frozen).into_iter().count() as u128, bv.count());
+    assert!(frozen.range(50..=50000).eq(bv.range(50..=50000)));
+    assert!(frozen.range(u64::MAX..).eq(vec![u64::MAX]));
+}
+
+#[test]
+fn empty_and_full() {
+    let bv = BitFooVec::new();
+    let bytes = bv.freeze();
+    let frozen = FrozenBitFooVec::new(&bytes).unwrap();
+    assert_eq!(frozen.count(), 0);
+    assert!(!frozen.get(0));
+    assert_eq!(frozen.rank(u64::MAX), 0);
+    assert_eq!(frozen.iter().next(), None);
+
+    let mut bv = BitFooVec::new();
+    bv.set_range(..);
+    let bytes = bv.freeze();
+    let frozen = FrozenBitFooVec::new(&bytes).unwrap();
+    assert_eq!(frozen.count(), 1 << 64);
+    assert!(frozen.get(u64::MAX));
+    assert_eq!(frozen.rank(u64::MAX), u64::MAX);
+    assert_eq!(frozen.count_range(..), 1 << 64);
+    assert!(frozen.range(u64::MAX - 2..).eq(vec![u64::MAX - 2, u64::MAX - 1, u64::MAX]));
+}
+
+#[test]
+fn bad_buffer() {
+    let bytes = sample_with_runs().freeze();
+
+    let mut bad = bytes.clone();
+    bad[0] = b'X';
+    assert!(matches!(FrozenBitFooVec::new(&bad), Err(BitFooError::BadMagic)));
+
+    let mut bad = bytes.clone();
+    bad[4] = 9;
+    assert!(matches!(FrozenBitFooVec::new(&bad), Err(BitFooError::UnsupportedVersion(9))));
+
+    // Truncated or extended buffers
+    for len in 0..bytes.len() {
+        assert!(FrozenBitFooVec::new(&bytes[..len]).is_err());
+    }
+    let mut bad = bytes.clone();
+    bad.push(0);
+    assert!(FrozenBitFooVec::new(&bad).is_err());
+
+    // A changed byte either fails the checks or still gives a
+    // well formed tree, so queries never see a bad offset
+    for pos in 0..bytes.len() {
+        let mut bad = bytes.clone();
+        bad[pos] ^= 0x81;
+        if let Ok(frozen) = FrozenBitFooVec::new(&bad) {
+            assert_eq!(frozen.iter().take(100000).count() as u128, 
+                frozen.count().min(100000), "byte {}", pos);
+            for bitno in POSITIONS.iter() {
+                frozen.get(*bitno);
+                frozen.rank(*bitno);
+            }
+        }
+    }
+}
+
+#[test]
+fn unchecked() {
+    let bytes = sample_with_runs().freeze();
+    let checked = FrozenBitFooVec::new(&bytes).unwrap();
+    let unchecked = FrozenBitFooVec::new_unchecked(&bytes).unwrap();
+    assert_eq!(checked.count(), unchecked.count());
+    for bitno in POSITIONS.iter() {
+        assert_eq!(checked.get(*bitno), unchecked.get(*bitno));
+        assert_eq!(checked.rank(*bitno), unchecked.rank(*bitno));
+    }
+
+    // Only the header is checked
+    let mut bad = bytes.clone();
+    bad[0] = b'X';
+    assert!(matches!(FrozenBitFooVec::new_unchecked(&bad), Err(BitFooError::BadMagic)));
+    assert!(FrozenBitFooVec::new_unchecked(&bytes[..16]).is_err());
+    let mut bad = bytes.clone();
+    bad.push(0);
+    assert!(FrozenBitFooVec::new(&bad).is_err());
+    assert!(FrozenBitFooVec::new_unchecked(&bad).is_ok());
+}
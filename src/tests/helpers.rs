@@ -0,0 +1,35 @@
+// Fixtures shared by the test modules
+use crate::BitFooVec;
+
+// Mix of single bits, a level 1 run, a level 2 run and bits
+// near the top of the u64 range, along with the bits set
+pub (crate) fn sample_vec() -> (BitFooVec, Vec<u64>) {
+    let mut bits = vec![3_u64, 64, 100, 127];
+    bits.extend(192..256);
+    bits.extend(16384*3..16384*4);
+    bits.extend(&[1 << 40, (1 << 40) + 63, (1 << 40) + 64, u64::MAX]);
+    (from_bits(&bits), bits)
+}
+
+// As above plus a run too large to list, which is held as
+// runs of high level children
+pub (crate) fn sample_with_runs() -> BitFooVec {
+    let (mut bv, _bits) = sample_vec();
+    bv.set_range(1 << 50..(1 << 51) + 5);
+    bv
+}
+
+pub (crate) fn from_bits(bits: &[u64]) -> BitFooVec {
+    let mut bv = BitFooVec::new();
+    for b in bits {
+        bv.set(*b);
+    }
+    bv
+}
+
+// Serialize a vector to bytes
+pub (crate) fn to_bytes(bv: &BitFooVec) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bv.write_to(&mut bytes).unwrap();
+    bytes
+}
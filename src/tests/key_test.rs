@@ -95,3 +95,48 @@ fn set_and_search() {
         assert_eq!(is_correct, true);
     }
 }
+
+#[test]
+fn iter_range() {
+    let mut index = KeyIndex::new(1);
+    for key in &[0_u8, 2, 128, 255] {
+        index.set(&Addr::new(*key as u64 * 64));
+    }
+    for key in &[5_u8, 130] {
+        index.run(&Addr::new(*key as u64 * 64));
+    }
+
+    // Node offsets continue from the number of nodes 
+    // before the start key
+    let states: Vec<(u8, Option<usize>)> = index.iter_range(2, 200)
+        .map(|state| match state {
+            KeyState::Node(key, offset) => (key, Some(offset)),
+            KeyState::Run(key) => (key, None),
+            KeyState::Missing(key, _) => (key, None),
+        })
+        .collect();
+    assert_eq!(states, vec![(2, Some(1)), (5, None), 
+        (128, Some(2)), (130, None)]);
+}
+
+#[test]
+fn iter_rev() {
+    let mut index = KeyIndex::new(1);
+    for key in &[0_u8, 2, 128, 255] {
+        index.set(&Addr::new(*key as u64 * 64));
+    }
+    for key in &[5_u8, 130] {
+        index.run(&Addr::new(*key as u64 * 64));
+    }
+
+    // Node offsets count back from the last node
+    let states: Vec<(u8, Option<usize>)> = index.iter().rev()
+        .map(|state| match state {
+            KeyState::Node(key, offset) => (key, Some(offset)),
+            KeyState::Run(key) => (key, None),
+            KeyState::Missing(key, _) => (key, None),
+        })
+        .collect();
+    assert_eq!(states, vec![(255, Some(3)), (130, None), (128, Some(2)),
+        (5, None), (2, Some(1)), (0, Some(0))]);
+}
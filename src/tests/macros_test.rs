@@ -0,0 +1,45 @@
+#[cfg(test)]
+use crate::BitFooVec;
+
+#[test]
+fn bits() {
+    let bv = bitfoovec![5, 98000, 42];
+    assert!(bv.iter().eq(vec![5, 42, 98000]));
+
+    let bv: BitFooVec = bitfoovec![];
+    assert_eq!(bv.count(), 0);
+    let bv = bitfoovec![u64::MAX, 0,];
+    assert!(bv.iter().eq(vec![0, u64::MAX]));
+}
+
+#[test]
+fn ranges() {
+    let bv = bitfoovec![0..100, 5000, 1 << 40..=(1 << 40) + 10];
+    assert!(bv.ranges().eq(vec![0..=99, 5000..=5000, 1 << 40..=(1 << 40) + 10]));
+
+    // Overlapping, adjacent and empty items are merged or dropped
+    let (lo, hi) = (40, 39);
+    let bv = bitfoovec![10..20, 15..=25, 26, 30..30, lo..=hi, 7];
+    assert!(bv.ranges().eq(vec![7..=7, 10..=26]));
+
+    let bv = bitfoovec![..10, u64::MAX - 5..];
+    assert!(bv.ranges().eq(vec![0..=9, u64::MAX - 5..=u64::MAX]));
+    let bv = bitfoovec![..];
+    assert_eq!(bv.count(), 1 << 64);
+
+    // Same as building bit by bit
+    let n = 1000;
+    let bv = bitfoovec![n * 3, n..n * 2];
+    let mut expected = BitFooVec::new();
+    expected.set(n * 3);
+    expected.set_range(n..n * 2);
+    assert!(bv.ranges().eq(expected.ranges()));
+}
+
+#[test]
+fn from_ranges() {
+    let bv = BitFooVec::from_ranges(vec![50..=60, 0..=5, 6..=6, 59..=70]);
+    assert!(bv.ranges().eq(vec![0..=6, 50..=70]));
+    let bv = BitFooVec::from_ranges(vec![0..=u64::MAX, 5..=10]);
+    assert_eq!(bv.count(), 1 << 64);
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+use crate::{BitFooVec,BitFooError};
+
+// {1, 2, 3, 1000} as a single array container
+#[cfg(test)]
+const ARRAY_FIXTURE: &[u8] = &[
+    0x3a, 0x30, 0, 0,       // No run cookie
+    1, 0, 0, 0,             // One container
+    0, 0, 3, 0,             // Key 0, cardinality 4
+    16, 0, 0, 0,            // Offset
+    1, 0, 2, 0, 3, 0, 0xe8, 0x03,
+];
+
+// {0-99, 131077} as a run container then an array container
+#[cfg(test)]
+const RUN_FIXTURE: &[u8] = &[
+    0x3b, 0x30, 1, 0,       // Run cookie, two containers
+    0b01,                   // First container is a run
+    0, 0, 99, 0,            // Key 0, cardinality 100
+    2, 0, 0, 0,             // Key 2, cardinality 1
+    1, 0, 0, 0, 99, 0,      // One run from 0 of length 100
+    5, 0,
+];
+
+#[test]
+fn array_fixture() {
+    let bv = BitFooVec::from_roaring_bytes(ARRAY_FIXTURE).unwrap();
+    assert!(bv.iter().eq(vec![1, 2, 3, 1000]));
+    assert_eq!(bv.to_roaring_bytes().unwrap(), ARRAY_FIXTURE);
+}
+
+#[test]
+fn run_fixture() {
+    let bv = BitFooVec::from_roaring_bytes(RUN_FIXTURE).unwrap();
+    assert!(bv.ranges().eq(vec![0..=99, 131077..=131077]));
+    assert_eq!(bv.to_roaring_bytes().unwrap(), RUN_FIXTURE);
+}
+
+#[test]
+fn bitmap_fixture() {
+    // Every other bit of 0-16383 needs a bitmap container
+    let mut fixture = vec![0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0];
+    fixture.extend_from_slice(&8191_u16.to_le_bytes());
+    fixture.extend_from_slice(&16_u32.to_le_bytes());
+    for wordno in 0..1024 {
+        let word: u64 = if wordno < 256 { 0x5555_5555_5555_5555 } else { 0 };
+        fixture.extend_from_slice(&word.to_le_bytes());
+    }
+
+    let bv = BitFooVec::from_roaring_bytes(&fixture).unwrap();
+    assert_eq!(bv.count(), 8192);
+    assert!(bv.iter().eq((0..16384).step_by(2)));
+    assert_eq!(bv.to_roaring_bytes().unwrap(), fixture);
+}
+
+#[test]
+fn treemap_fixture() {
+    // {5} in bucket 0 and {2^40 + 7} in bucket 256
+    let fixture: &[u8] = &[
+        2, 0, 0, 0, 0, 0, 0, 0,  // Two buckets
+        0, 0, 0, 0,              // High key 0
+        0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 5, 0,
+        0, 1, 0, 0,              // High key 256
+        0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 7, 0,
+    ];
+    let bv = BitFooVec::from_roaring_treemap_bytes(fixture).unwrap();
+    assert!(bv.iter().eq(vec![5, (1 << 40) + 7]));
+    assert_eq!(bv.to_roaring_treemap_bytes().unwrap(), fixture);
+    assert!(matches!(bv.to_roaring_bytes(), Err(BitFooError::OutOfRange(b)) if b == (1 << 40) + 7));
+
+    let empty = BitFooVec::new();
+    assert_eq!(empty.to_roaring_treemap_bytes().unwrap(), vec![0; 8]);
+    assert_eq!(BitFooVec::from_roaring_treemap_bytes(&[0; 8]).unwrap().count(), 0);
+}
+
+#[test]
+fn round_trip() {
+    // Mix of every container type, including full containers
+    // which become runs in both formats
+    let mut bv = BitFooVec::new();
+    bv.set_range(10..=20);
+    bv.set_range(1 << 16..3 << 16);
+    for bitno in (1 << 20..(1 << 20) + 20000).step_by(3) {
+        bv.set(bitno);
+    }
+    for bitno in [70000, 70002, 1 << 31, u32::MAX as u64].iter() {
+        bv.set(*bitno);
+    }
+
+    let bytes = bv.to_roaring_bytes().unwrap();
+    let loaded = BitFooVec::from_roaring_bytes(&bytes).unwrap();
+    assert!(loaded.ranges().eq(bv.ranges()));
+
+    bv.set_range(u64::MAX - 100000..);
+    bv.set(1 << 50);
+    let bytes = bv.to_roaring_treemap_bytes().unwrap();
+    let loaded = BitFooVec::from_roaring_treemap_bytes(&bytes).unwrap();
+    assert!(loaded.ranges().eq(bv.ranges()));
+}
+
+#[test]
+fn too_large() {
+    // Both formats fail up front rather than building
+    // a container for every 2^16 bits
+    let mut full = BitFooVec::new();
+    full.set_range(..);
+    assert!(matches!(full.to_roaring_bytes(), Err(BitFooError::OutOfRange(u64::MAX))));
+    assert!(matches!(full.to_roaring_treemap_bytes(), Err(BitFooError::TooLarge(_))));
+
+    // A run filling 2^24 containers, plus one more
+    let mut bv = BitFooVec::new();
+    bv.set_range(..1 << 40);
+    bv.set(u64::MAX);
+    assert!(matches!(bv.to_roaring_treemap_bytes(), Err(BitFooError::TooLarge(_))));
+    bv.clear(u64::MAX);
+    assert!(matches!(bv.to_roaring_bytes(), Err(BitFooError::OutOfRange(b)) if b == (1 << 40) - 1));
+}
+
+#[test]
+fn bad_input() {
+    let corrupt = |bytes: &[u8]| {
+        matches!(BitFooVec::from_roaring_bytes(bytes), Err(BitFooError::Corrupt(_)))
+    };
+
+    let mut bad = ARRAY_FIXTURE.to_vec();
+    bad[0] = 0;
+    assert!(matches!(BitFooVec::from_roaring_bytes(&bad), Err(BitFooError::BadMagic)));
+
+    // Truncated input at every length
+    for len in 0..ARRAY_FIXTURE.len() {
+        assert!(BitFooVec::from_roaring_bytes(&ARRAY_FIXTURE[..len]).is_err());
+    }
+
+    let mut bad = ARRAY_FIXTURE.to_vec();
+    bad.push(0);
+    assert!(corrupt(&bad));
+
+    // Array values out of order
+    let mut bad = ARRAY_FIXTURE.to_vec();
+    bad[18] = 5;
+    assert!(corrupt(&bad));
+
+    // Run cardinality doesn't match the header
+    let mut bad = RUN_FIXTURE.to_vec();
+    bad[7] = 50;
+    assert!(corrupt(&bad));
+
+    // Run past the end of the container
+    let mut bad = RUN_FIXTURE.to_vec();
+    bad[15] = 0xff;
+    bad[16] = 0xff;
+    assert!(corrupt(&bad));
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+use crate::{BitFooVec,BitVec256,Addr};
+
+#[test]
+fn json_ranges() {
+    let mut bv = BitFooVec::new();
+    bv.set_range(0..100);
+    bv.set(1024);
+    bv.set(u64::MAX);
+
+    let json = serde_json::to_string(&bv).unwrap();
+    assert_eq!(json, "[[0,99],[1024,1024],[18446744073709551615,18446744073709551615]]");
+    let loaded: BitFooVec = serde_json::from_str(&json).unwrap();
+    assert!(loaded.ranges().eq(bv.ranges()));
+
+    // Overlapping and unsorted ranges are merged
+    let loaded: BitFooVec = serde_json::from_str("[[50,60],[0,55],[7,7]]").unwrap();
+    assert!(loaded.ranges().eq(vec![0..=60]));
+
+    assert!(serde_json::from_str::<BitFooVec>("[[5,4]]").is_err());
+    assert!(serde_json::from_str::<BitFooVec>("[[1,2,3]]").is_err());
+}
+
+#[test]
+fn binary_tree() {
+    let mut bv = BitFooVec::new();
+    bv.set_range(1000..1 << 40);
+    bv.set(u64::MAX - 1);
+
+    let bytes = bincode::serialize(&bv).unwrap();
+    let loaded: BitFooVec = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(loaded.level(), bv.level());
+    assert!(loaded.ranges().eq(bv.ranges()));
+
+    // Damaged trees are rejected
+    let mut bad = bytes.clone();
+    let len = bad.len();
+    bad[len - 10] ^= 1;
+    assert!(bincode::deserialize::<BitFooVec>(&bad).is_err());
+    assert!(bincode::deserialize::<BitFooVec>(&bytes[..len - 1]).is_err());
+
+    // As are extra bytes after the tree
+    let mut tree = Vec::new();
+    bv.write_to(&mut tree).unwrap();
+    tree.push(0);
+    assert!(bincode::deserialize::<BitFooVec>(&bincode::serialize(&tree).unwrap()).is_err());
+}
+
+#[test]
+fn bitvec256() {
+    let mut vec = BitVec256::new();
+    for bitno in [0, 63, 64, 200, 255].iter() {
+        vec.set(*bitno);
+    }
+
+    let json = serde_json::to_string(&vec).unwrap();
+    assert_eq!(json, "[0,63,64,200,255]");
+    let loaded: BitVec256 = serde_json::from_str(&json).unwrap();
+    assert!(loaded.iter().eq(vec.iter()));
+
+    let bytes = bincode::serialize(&vec).unwrap();
+    assert_eq!(bytes.len(), 32);
+    let loaded: BitVec256 = bincode::deserialize(&bytes).unwrap();
+    assert!(loaded.iter().eq(vec.iter()));
+}
+
+#[test]
+fn addr() {
+    let addr = Addr::new(0x1234_5678_9abc);
+    let json = serde_json::to_string(&addr).unwrap();
+    assert_eq!(json, "20015998343868");
+    let loaded: Addr = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.bitno(), addr.bitno());
+    assert_eq!(loaded.node_level, addr.node_level);
+
+    let loaded: Addr = bincode::deserialize(&bincode::serialize(&addr).unwrap()).unwrap();
+    assert_eq!(loaded.bitno(), addr.bitno());
+}
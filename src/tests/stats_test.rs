@@ -0,0 +1,56 @@
+#[cfg(test)]
+use crate::{BitFooVec,Node};
+#[cfg(test)]
+use std::mem::size_of;
+
+#[test]
+fn empty() {
+    let bv = BitFooVec::new();
+    let stats = bv.stats();
+    assert_eq!(stats.root_level, 1);
+    assert_eq!(stats.nodes[1], 1);
+    assert_eq!(stats.node_count(), 1);
+    assert_eq!(stats.run_count(), 0);
+    assert_eq!(stats.leaf_words, 0);
+    assert_eq!(stats.average_fill(), 0.0);
+    assert_eq!(stats.heap_size, bv.heap_size());
+}
+
+#[test]
+fn shape() {
+    // Level 3 root with a level 2 node under key 0 and another 
+    // for the bit at 2^22
+    let mut bv = BitFooVec::new();
+    bv.set_range(0..128);
+    bv.set(200);
+    bv.set_range(1 << 14..2 << 14);
+    bv.set((1 << 22) + 5);
+
+    let stats = bv.stats();
+    assert_eq!(stats.root_level, 3);
+    assert_eq!(stats.nodes[..4], [0, 2, 2, 1]);
+    assert_eq!(stats.runs[..4], [0, 2, 1, 0]);
+    assert_eq!(stats.leaf_words, 2);
+    assert_eq!(stats.average_fill(), 2.0 / 512.0);
+
+    // Heap is the child vectors plus leaf words
+    assert_eq!(stats.heap_size, bv.heap_size());
+    assert!(stats.heap_size >= 4 * size_of::<Node>() + 2 * size_of::<u64>());
+    assert!(stats.wasted_capacity < stats.heap_size);
+
+    let report = stats.to_string();
+    assert!(report.starts_with("root level: 3\nlevel 3: 1 nodes, 0 runs\n"));
+    assert!(report.contains("leaf words: 2 (0.4% fill)"));
+}
+
+#[test]
+fn runs_are_cheap() {
+    // A full vector is a handful of runs with no leaf words
+    let mut bv = BitFooVec::new();
+    bv.set_range(..);
+    let stats = bv.stats();
+    assert_eq!(stats.root_level, 8);
+    assert_eq!(stats.leaf_words, 0);
+    assert_eq!(stats.runs[8], 4);
+    assert!(bv.heap_size() < 1024);
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+use crate::{BitFooVec,BitFooError};
+
+#[test]
+fn display() {
+    let mut bv = BitFooVec::new();
+    assert_eq!(bv.to_string(), "{}");
+
+    bv.set_range(0..100);
+    bv.set(1024);
+    bv.set_range(1 << 40..=(1 << 40) + 7);
+    assert_eq!(bv.to_string(), "{0-99, 1024, 2^40-2^40+7}");
+
+    // Runs print as one range however large
+    let mut bv = BitFooVec::new();
+    bv.set_range(..);
+    assert_eq!(bv.to_string(), "{0-18446744073709551615}");
+    bv.clear(1 << 50);
+    assert_eq!(bv.to_string(), 
+        "{0-1125899906842623, 2^50+1-18446744073709551615}");
+
+    // Powers of 2 only when shorter than the decimal
+    let bv: BitFooVec = "{512, 65536, 2^17+1, 2^17+100000, 2^20+1, 2^63}".parse().unwrap();
+    assert_eq!(bv.to_string(), "{512, 2^16, 131073, 231072, 2^20+1, 2^63}");
+}
+
+#[test]
+fn display_truncated() {
+    let mut bv = BitFooVec::new();
+    for bitno in (0..100).map(|b| b * 10) {
+        bv.set_range(bitno..bitno + 5);
+    }
+
+    let text = bv.to_string();
+    assert!(text.starts_with("{0-4, 10-14, 20-24, "));
+    assert!(text.ends_with(", 310-314, ...} (100 ranges, 500 bits)"));
+
+    // Alternate form prints everything
+    let text = format!("{:#}", bv);
+    assert!(text.ends_with(", 980-984, 990-994}"));
+    assert_eq!(text.parse::<BitFooVec>().unwrap().count(), 500);
+
+    let debug = format!("{:?}", bv);
+    assert!(debug.starts_with("BitFooVec { level: 1, count: 500, bits: {0-4, "));
+}
+
+#[test]
+fn parse() {
+    let bv: BitFooVec = "{0-99, 1024, 2^40-2^40+7}".parse().unwrap();
+    assert!(bv.ranges().eq(vec![0..=99, 1024..=1024, 1 << 40..=(1 << 40) + 7]));
+
+    // Spacing is optional, and ranges may overlap or be unsorted
+    let bv: BitFooVec = " {  7,3 -5,4-6 , 18446744073709551615 } ".parse().unwrap();
+    assert!(bv.ranges().eq(vec![3..=7, u64::MAX..=u64::MAX]));
+    assert_eq!("{}".parse::<BitFooVec>().unwrap().count(), 0);
+    assert_eq!("{2^63+9223372036854775807}".parse::<BitFooVec>().unwrap().count(), 1);
+
+    // Round trip through Display
+    let mut bv = BitFooVec::new();
+    bv.set_range(5..1 << 45);
+    bv.set(u64::MAX - 3);
+    for exp in 0..64 {
+        bv.set((1 << exp) + 3);
+    }
+    assert!(bv.to_string().parse::<BitFooVec>().unwrap().ranges().eq(bv.ranges()));
+}
+
+#[test]
+fn parse_errors() {
+    let cases = [
+        ("", 0, "expected '{'"),
+        ("0-5}", 0, "expected '{'"),
+        ("{1, 2", 5, "expected ',' or '}'"),
+        ("{1 2}", 3, "expected ',' or '}'"),
+        ("{1,}", 3, "expected a bit number"),
+        ("{-5}", 1, "expected a bit number"),
+        ("{9-5}", 1, "range start is after its end"),
+        ("{18446744073709551616}", 1, "bit number is too large"),
+        ("{3^4}", 1, "only powers of 2 are supported"),
+        ("{2^64}", 3, "exponent must be below 64"),
+        ("{2^63+2^63}", 7, "expected ',' or '}'"),
+        ("{2^63+9223372036854775808}", 6, "bit number is too large"),
+        ("{1} x", 4, "unexpected text after '}'"),
+        ("{0-99, ...} (1000 ranges, 5 bits)", 7, "expected a bit number"),
+    ];
+
+    for (text, offset, reason) in cases.iter() {
+        match text.parse::<BitFooVec>() {
+            Err(BitFooError::Parse { offset: o, reason: r }) => {
+                assert_eq!((o, r), (*offset, *reason), "{:?}", text);
+            },
+            other => panic!("{:?} gave {:?}", text, other),
+        }
+    }
+
+    let err = "{1,}".parse::<BitFooVec>().unwrap_err();
+    assert_eq!(err.to_string(), "parse error at offset 3: expected a bit number");
+}
@@ -0,0 +1,190 @@
+use crate::{BitFooVec,BitFooError};
+use std::fmt;
+use std::str::FromStr;
+
+// Text form of a vector as a set of ranges, e.g. {0-99, 1024,
+// 2^40-2^40+7}. Bits are written as 2^N or 2^N+M when that is
+// shorter than the decimal. Display stops after DISPLAY_RANGES
+// ranges and adds a count, while the alternate form ({:#}) prints
+// every range. FromStr reads the full form back, so it takes the
+// same 2^N and 2^N+M terms.
+const DISPLAY_RANGES: usize = 32;
+
+// Display interface
+impl fmt::Display for BitFooVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match f.alternate() {
+            true => usize::MAX,
+            false => DISPLAY_RANGES,
+        };
+
+        let mut ranges = self.ranges();
+        write!(f, "{{")?;
+        for (i, range) in ranges.by_ref().take(limit).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_bitno(f, *range.start())?;
+            if range.start() != range.end() {
+                write!(f, "-")?;
+                write_bitno(f, *range.end())?;
+            }
+        }
+
+        // Summarize whatever didn't fit
+        let more = ranges.count();
+        if more > 0 {
+            write!(f, ", ...}} ({} ranges, {} bits)", limit + more, self.count())
+        }
+        else {
+            write!(f, "}}")
+        }
+    }
+}
+
+// Debug interface
+impl fmt::Debug for BitFooVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BitFooVec {{ level: {}, count: {}, bits: {} }}",
+               self.level(), self.count(), self)
+    }
+}
+
+// Write a bit number as 2^N or 2^N+M if shorter than decimal
+fn write_bitno(f: &mut fmt::Formatter<'_>, bitno: u64) -> fmt::Result {
+    let decimal = bitno.to_string();
+    if bitno > 0 {
+        let exp = 63 - bitno.leading_zeros();
+        let power = match bitno - (1 << exp) {
+            0 => format!("2^{}", exp),
+            offset => format!("2^{}+{}", exp, offset),
+        };
+        if power.len() < decimal.len() {
+            return write!(f, "{}", power);
+        }
+    }
+    write!(f, "{}", decimal)
+}
+
+// FromStr interface
+impl FromStr for BitFooVec {
+    type Err = BitFooError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { text: s.as_bytes(), pos: 0 };
+        let mut bv = BitFooVec::new();
+
+        parser.expect(b'{', "expected '{'")?;
+        if !parser.eat(b'}') {
+            loop {
+                let start_pos = parser.pos;
+                let start = parser.term()?;
+                let end = match parser.eat(b'-') {
+                    true => parser.term()?,
+                    false => start,
+                };
+                if start > end {
+                    return Err(parse_error(start_pos, "range start is after its end"));
+                }
+                bv.set_range(start..=end);
+
+                if parser.eat(b'}') {
+                    break;
+                }
+                parser.expect(b',', "expected ',' or '}'")?;
+            }
+        }
+
+        parser.skip_space();
+        match parser.pos == s.len() {
+            true => Ok(bv),
+            false => Err(parse_error(parser.pos, "unexpected text after '}'")),
+        }
+    }
+}
+
+// Cursor over the text being parsed
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_space(&mut self) {
+        while self.pos < self.text.len() && self.text[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    // Consume the next non-space character if it matches
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_space();
+        if self.text.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8, reason: &'static str) -> Result<(), BitFooError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(parse_error(self.pos, reason)),
+        }
+    }
+
+    // A bit number: N, 2^N or 2^N+M
+    fn term(&mut self) -> Result<u64, BitFooError> {
+        let start = self.pos;
+        let value = self.number()?;
+        if !self.eat(b'^') {
+            return Ok(value);
+        }
+
+        let exp_pos = self.pos;
+        let exp = self.number()?;
+        let base = match (value, exp) {
+            (2, 0..=63) => 1_u64 << exp,
+            (2, _) => return Err(parse_error(exp_pos, "exponent must be below 64")),
+            _ => return Err(parse_error(start, "only powers of 2 are supported")),
+        };
+
+        match self.eat(b'+') {
+            true => {
+                let offset_pos = self.pos;
+                base.checked_add(self.number()?)
+                    .ok_or_else(|| parse_error(offset_pos, "bit number is too large"))
+            },
+            false => Ok(base),
+        }
+    }
+
+    // Unsigned decimal number
+    fn number(&mut self) -> Result<u64, BitFooError> {
+        self.skip_space();
+        let start = self.pos;
+        let mut value: u64 = 0;
+
+        while let Some(c) = self.text.get(self.pos).filter(|c| c.is_ascii_digit()) {
+            value = value.checked_mul(10)
+                .and_then(|v| v.checked_add((c - b'0') as u64))
+                .ok_or_else(|| parse_error(start, "bit number is too large"))?;
+            self.pos += 1;
+        }
+
+        match self.pos > start {
+            true => Ok(value),
+            false => Err(parse_error(start, "expected a bit number")),
+        }
+    }
+}
+
+fn parse_error(offset: usize, reason: &'static str) -> BitFooError {
+    BitFooError::Parse { offset, reason }
+}
+
+#[cfg(test)]
+#[path = "./tests/text_test.rs"]
+mod tests;